@@ -0,0 +1,324 @@
+use crate::fsutil::write_atomic;
+use crate::timestamp::now_rfc3339;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// One saved copy of a shell config file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupRecord {
+    /// Unique id, also the file name in the backups directory (e.g. "20260326T140509Z-.bashrc")
+    pub id: String,
+    /// File that was backed up
+    pub source: PathBuf,
+    /// RFC 3339 UTC timestamp
+    pub created_at: String,
+}
+
+/// Rotating backups of shell config files: ~/.akash/backups/
+/// Each backup is a plain copy, listed in index.json with the path it came from.
+pub struct Backups {
+    dir: PathBuf,
+}
+
+impl Backups {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Default location: ~/.akash/backups
+    pub fn open_default() -> Result<Self> {
+        let home =
+            dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Cannot determine home directory"))?;
+        Ok(Self::new(home.join(".akash").join("backups")))
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join("index.json")
+    }
+
+    /// All backups, oldest first.
+    pub fn list(&self) -> Result<Vec<BackupRecord>> {
+        let path = self.index_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse backup index {}", path.display()))
+    }
+
+    fn save_index(&self, records: &[BackupRecord]) -> Result<()> {
+        let content =
+            serde_json::to_string_pretty(records).context("Failed to serialize backup index")?;
+        write_atomic(&self.index_path(), content.as_bytes())
+    }
+
+    /// Most recent backup of `source`, if any.
+    pub fn latest_for(&self, source: &Path) -> Result<Option<BackupRecord>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .rev()
+            .find(|record| record.source == source))
+    }
+
+    /// Copy `source` into the backups directory, keeping at most `retention`
+    /// backups per file. Returns None when there was nothing new to save:
+    /// the file does not exist, is identical to its latest backup, or retention is 0.
+    pub fn create(&self, source: &Path, retention: usize) -> Result<Option<BackupRecord>> {
+        if retention == 0 || !source.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read(source)
+            .with_context(|| format!("Failed to read {}", source.display()))?;
+
+        if let Some(latest) = self.latest_for(source)? {
+            let previous = std::fs::read(self.dir.join(&latest.id)).unwrap_or_default();
+            if previous == content {
+                debug!("{} unchanged since backup {}", source.display(), latest.id);
+                return Ok(None);
+            }
+        }
+
+        self.create_dir()?;
+
+        let mut records = self.list()?;
+        let created_at = now_rfc3339();
+        let id = self.unique_id(&records, &created_at, source);
+
+        write_copy(&self.dir.join(&id), &content, source)?;
+        let record = BackupRecord {
+            id,
+            source: source.to_path_buf(),
+            created_at,
+        };
+        records.push(record.clone());
+        info!("Backed up {} as {}", source.display(), record.id);
+
+        self.rotate(&mut records, source, retention);
+        self.save_index(&records)?;
+        Ok(Some(record))
+    }
+
+    /// Create the backups directory, readable by its owner only on Unix:
+    /// backups of rc files can hold tokens. An existing directory is tightened too.
+    fn create_dir(&self) -> Result<()> {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder
+            .create(&self.dir)
+            .with_context(|| format!("Failed to create directory {}", self.dir.display()))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&self.dir, std::fs::Permissions::from_mode(0o700))
+                .with_context(|| format!("Failed to set permissions on {}", self.dir.display()))?;
+        }
+        Ok(())
+    }
+
+    /// "<timestamp>-<file name>", with a counter if several backups share the same second.
+    fn unique_id(&self, records: &[BackupRecord], created_at: &str, source: &Path) -> String {
+        let stamp: String = created_at
+            .chars()
+            .filter(|c| *c != '-' && *c != ':')
+            .collect();
+        let file_name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("config"));
+
+        let base = format!("{}-{}", stamp, file_name);
+        let mut id = base.clone();
+        let mut counter = 2;
+        while records.iter().any(|record| record.id == id) || self.dir.join(&id).exists() {
+            id = format!("{}-{}", base, counter);
+            counter += 1;
+        }
+        id
+    }
+
+    /// Drop the oldest backups of `source` beyond `retention`.
+    fn rotate(&self, records: &mut Vec<BackupRecord>, source: &Path, retention: usize) {
+        let count = records.iter().filter(|r| r.source == source).count();
+        let mut excess = count.saturating_sub(retention);
+
+        records.retain(|record| {
+            if excess > 0 && record.source == source {
+                excess -= 1;
+                debug!("Rotating out backup {}", record.id);
+                let _ = std::fs::remove_file(self.dir.join(&record.id));
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Write a backup back to the file it came from.
+    /// The current file is backed up first, so a restore can itself be undone.
+    pub fn restore(&self, id: &str, retention: usize) -> Result<BackupRecord> {
+        let record = self
+            .list()?
+            .into_iter()
+            .find(|record| record.id == id)
+            .ok_or_else(|| anyhow::anyhow!("No backup with id '{}'", id))?;
+
+        let backup_path = self.dir.join(&record.id);
+        let content = std::fs::read(&backup_path)
+            .with_context(|| format!("Failed to read backup {}", backup_path.display()))?;
+
+        // Content is already in memory, so rotation cannot lose the backup being restored
+        self.create(&record.source, retention)?;
+
+        if let Some(parent) = record.source.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        write_atomic(&record.source, &content)
+            .with_context(|| format!("Failed to restore {}", record.source.display()))?;
+
+        info!("Restored {} from {}", record.source.display(), record.id);
+        Ok(record)
+    }
+}
+
+/// Write a backup of `source` to the new file `path`, with the mode of `source`:
+/// a 0600 rc file must not be copied out readable by other users.
+/// The file is created with that mode, so its content is never exposed, even briefly.
+fn write_copy(path: &Path, content: &[u8], source: &Path) -> Result<()> {
+    let permissions = std::fs::metadata(source)
+        .with_context(|| format!("Failed to read {}", source.display()))?
+        .permissions();
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(permissions.mode() & 0o777);
+    }
+
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create backup {}", path.display()))?;
+    // The umask may have dropped bits of the source's mode
+    std::fs::set_permissions(path, permissions)
+        .with_context(|| format!("Failed to set permissions on {}", path.display()))?;
+    file.write_all(content)
+        .with_context(|| format!("Failed to write backup {}", path.display()))?;
+    file.sync_all()
+        .with_context(|| format!("Failed to flush backup {}", path.display()))
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("akash-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn given_more_backups_than_retention_when_creating_then_oldest_are_removed() {
+        // Given
+        let dir = test_dir("backup-rotate");
+        let backups = Backups::new(dir.join("backups"));
+        let rc = dir.join(".bashrc");
+
+        // When
+        for version in 1..=4 {
+            std::fs::write(&rc, format!("version {}", version)).unwrap();
+            backups.create(&rc, 2).unwrap();
+        }
+
+        // Then
+        let records = backups.list().unwrap();
+        let contents: Vec<String> = records
+            .iter()
+            .map(|r| std::fs::read_to_string(dir.join("backups").join(&r.id)).unwrap())
+            .collect();
+        let files = std::fs::read_dir(dir.join("backups")).unwrap().count();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(contents, vec!["version 3", "version 4"]);
+        // 2 backups + index.json
+        assert_eq!(files, 3);
+    }
+
+    #[test]
+    fn given_unchanged_file_when_creating_backup_then_nothing_is_saved() {
+        // Given
+        let dir = test_dir("backup-unchanged");
+        let backups = Backups::new(dir.join("backups"));
+        let rc = dir.join(".zshrc");
+        std::fs::write(&rc, "same").unwrap();
+        backups.create(&rc, 5).unwrap();
+
+        // When
+        let second = backups.create(&rc, 5).unwrap();
+
+        // Then
+        let count = backups.list().unwrap().len();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(second.is_none());
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn given_backup_when_restoring_then_file_is_rolled_back_and_current_is_saved() {
+        // Given
+        let dir = test_dir("backup-restore");
+        let backups = Backups::new(dir.join("backups"));
+        let rc = dir.join(".bashrc");
+        std::fs::write(&rc, "good").unwrap();
+        let record = backups.create(&rc, 5).unwrap().unwrap();
+        std::fs::write(&rc, "broken").unwrap();
+
+        // When
+        backups.restore(&record.id, 5).unwrap();
+
+        // Then
+        let content = std::fs::read_to_string(&rc).unwrap();
+        let latest = backups.latest_for(&rc).unwrap().unwrap();
+        let saved = std::fs::read_to_string(dir.join("backups").join(&latest.id)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(content, "good");
+        assert_eq!(saved, "broken");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn given_private_file_when_creating_backup_then_backup_and_directory_stay_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Given
+        let dir = test_dir("backup-perms");
+        let backups = Backups::new(dir.join("backups"));
+        let rc = dir.join(".bashrc");
+        std::fs::write(&rc, "export TOKEN=secret").unwrap();
+        std::fs::set_permissions(&rc, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        // When
+        let record = backups.create(&rc, 5).unwrap().unwrap();
+
+        // Then
+        let mode = |path: PathBuf| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let file_mode = mode(dir.join("backups").join(&record.id));
+        let dir_mode = mode(dir.join("backups"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(file_mode, 0o600);
+        assert_eq!(dir_mode, 0o700);
+    }
+}
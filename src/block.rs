@@ -0,0 +1,506 @@
+use crate::shell::Shell;
+use anyhow::{Result, bail};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Position of the akash-managed block in a shell config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockSpan {
+    /// Bytes from the start of the BEGIN line to the end of the END marker
+    /// (the END line's terminator is not included, so it is kept on replace)
+    pub outer: Range<usize>,
+    /// Bytes of the lines between the markers
+    pub inner: Range<usize>,
+}
+
+impl BlockSpan {
+    /// Lines between the markers, without their line endings.
+    pub fn lines<'a>(&self, content: &'a str) -> Vec<&'a str> {
+        content[self.inner.clone()].lines().collect()
+    }
+}
+
+/// Locate the managed block, or None when the file has no markers.
+///
+/// Markers only count when they are a whole line (surrounding whitespace and
+/// CRLF aside), so a marker string quoted inside an alias command is ignored.
+/// Anything ambiguous (duplicated, unpaired or out-of-order markers) is an error:
+/// guessing which part of the file akash owns could destroy user content.
+pub fn locate_block(
+    content: &str,
+    begin_marker: &str,
+    end_marker: &str,
+) -> Result<Option<BlockSpan>> {
+    let mut begins = Vec::new();
+    let mut ends = Vec::new();
+
+    let mut offset = 0;
+    for (index, raw) in content.split_inclusive('\n').enumerate() {
+        let line = raw.trim();
+        let start = offset;
+        offset += raw.len();
+        // Line number, start of the line, end of the line's text
+        let position = (
+            index + 1,
+            start,
+            start + raw.trim_end_matches(['\n', '\r']).len(),
+        );
+        if line == begin_marker {
+            begins.push(position);
+        } else if line == end_marker {
+            ends.push(position);
+        }
+    }
+
+    let line_numbers = |markers: &[(usize, usize, usize)]| {
+        markers
+            .iter()
+            .map(|(number, _, _)| number.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match (begins.as_slice(), ends.as_slice()) {
+        ([], []) => Ok(None),
+        ([_, _, ..], _) => bail!(
+            "'{}' appears more than once (lines {}): keep a single akash block",
+            begin_marker,
+            line_numbers(&begins)
+        ),
+        (_, [_, _, ..]) => bail!(
+            "'{}' appears more than once (lines {}): keep a single akash block",
+            end_marker,
+            line_numbers(&ends)
+        ),
+        ([(line, _, _)], []) => bail!(
+            "'{}' on line {} has no matching '{}'",
+            begin_marker,
+            line,
+            end_marker
+        ),
+        ([], [(line, _, _)]) => bail!(
+            "'{}' on line {} has no matching '{}' before it",
+            end_marker,
+            line,
+            begin_marker
+        ),
+        ([(begin_line, _, _)], [(end_line, _, _)]) if end_line < begin_line => bail!(
+            "'{}' on line {} comes before '{}' on line {}",
+            end_marker,
+            end_line,
+            begin_marker,
+            begin_line
+        ),
+        ([(_, begin_start, begin_text_end)], [(_, end_start, end_text_end)]) => {
+            // The inner lines start after the BEGIN line's terminator
+            let inner_start = content[*begin_text_end..]
+                .find('\n')
+                .map_or(content.len(), |i| begin_text_end + i + 1)
+                .min(*end_start);
+            Ok(Some(BlockSpan {
+                outer: *begin_start..*end_text_end,
+                inner: inner_start..*end_start,
+            }))
+        }
+    }
+}
+
+/// Line ending used by a file: CRLF if its first line ends with one.
+fn line_ending(content: &str) -> &'static str {
+    match content.find('\n') {
+        Some(i) if content[..i].ends_with('\r') => "\r\n",
+        _ => "\n",
+    }
+}
+
+/// Replace the akash block between markers, or append it if there is none.
+/// Everything outside the block is kept byte for byte, and the block takes the
+/// file's line endings. Fails without touching anything on ambiguous markers.
+pub fn replace_or_append_block(
+    content: &str,
+    begin_marker: &str,
+    end_marker: &str,
+    new_block: &str,
+) -> Result<String> {
+    let eol = line_ending(content);
+    let new_block = new_block.replace('\n', eol);
+
+    match locate_block(content, begin_marker, end_marker)? {
+        Some(span) => Ok(format!(
+            "{}{}{}",
+            &content[..span.outer.start],
+            new_block,
+            &content[span.outer.end..]
+        )),
+        None => {
+            let mut result = content.to_string();
+            if !result.is_empty() {
+                if !result.ends_with('\n') {
+                    result.push_str(eol);
+                }
+                result.push_str(eol);
+            }
+            result.push_str(&new_block);
+            result.push_str(eol);
+            Ok(result)
+        }
+    }
+}
+
+/// A managed block split back into the definitions it contains.
+#[derive(Debug, Default, PartialEq)]
+pub struct BlockEntries {
+    /// Definition text by name, as `Shell::generate_entries` renders it
+    pub entries: BTreeMap<String, String>,
+    /// Lines before the first definition that the shell cannot attribute to a name
+    pub unrecognized: Vec<String>,
+}
+
+/// Group block lines by the name they define. A line that starts no definition
+/// (e.g. the body of a multi-line function) belongs to the one above it, and so does
+/// every line inside a function, whatever it looks like.
+pub fn split_entries(shell: &dyn Shell, lines: &[&str]) -> BlockEntries {
+    let mut result = BlockEntries::default();
+    let mut current: Option<String> = None;
+    let mut depth: isize = 0;
+
+    for line in lines {
+        let top_level = depth == 0;
+        depth = (depth + shell.nesting(line)).max(0);
+
+        if top_level && let Some(name) = shell.defined_name(line) {
+            // A name defined twice (e.g. pasted by hand) keeps both definitions,
+            // so it never matches the generated one
+            if current.as_deref() != Some(name.as_str()) {
+                if let Some(text) = result.entries.get_mut(&name) {
+                    text.push('\n');
+                    text.push_str(line);
+                } else {
+                    result.entries.insert(name.clone(), line.to_string());
+                }
+                current = Some(name);
+                continue;
+            }
+        }
+
+        match current
+            .as_ref()
+            .and_then(|name| result.entries.get_mut(name))
+        {
+            Some(text) => {
+                text.push('\n');
+                text.push_str(line);
+            }
+            None if line.trim().is_empty() => {}
+            None => result.unrecognized.push(line.to_string()),
+        }
+    }
+    result
+}
+
+/// Remove the akash block, or None if the file has none.
+/// The blank line that `replace_or_append_block` puts before an appended block
+/// is removed with it, so init followed by uninstall restores the original file.
+pub fn remove_block(content: &str, begin_marker: &str, end_marker: &str) -> Result<Option<String>> {
+    let Some(span) = locate_block(content, begin_marker, end_marker)? else {
+        return Ok(None);
+    };
+
+    let mut before = &content[..span.outer.start];
+    let after = &content[span.outer.end..];
+    let after = after
+        .strip_prefix("\r\n")
+        .or_else(|| after.strip_prefix('\n'))
+        .unwrap_or(after);
+
+    if after.is_empty() {
+        for eol in ["\r\n", "\n"] {
+            if before.ends_with(&eol.repeat(2)) {
+                before = &before[..before.len() - eol.len()];
+                break;
+            }
+        }
+    }
+    Ok(Some(format!("{}{}", before, after)))
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::{ShellType, get_shell};
+    use crate::store::AliasStore;
+
+    const BEGIN: &str = "# BEGIN akash aliases";
+    const END: &str = "# END akash aliases";
+
+    /// Run replace_or_append_block with the default markers on every case.
+    /// Ok holds the expected output, Err a substring of the expected error.
+    fn check_cases(cases: &[(&str, &str, Result<&str, &str>)]) {
+        let block = "# BEGIN akash aliases\nalias gs='git status'\n# END akash aliases";
+        for (case, input, expected) in cases {
+            let result = replace_or_append_block(input, BEGIN, END, block);
+            match (expected, result) {
+                (Ok(output), Ok(actual)) => assert_eq!(&actual, output, "case: {}", case),
+                (Err(message), Err(error)) => assert!(
+                    error.to_string().contains(message),
+                    "case: {}: unexpected error '{}'",
+                    case,
+                    error
+                ),
+                (expected, actual) => {
+                    panic!("case: {}: expected {:?}, got {:?}", case, expected, actual)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn given_well_formed_files_when_replacing_then_only_the_block_changes() {
+        check_cases(&[
+            (
+                "empty file",
+                "",
+                Ok("# BEGIN akash aliases\nalias gs='git status'\n# END akash aliases\n"),
+            ),
+            (
+                "append after content",
+                "export A=1\n",
+                Ok(
+                    "export A=1\n\n# BEGIN akash aliases\nalias gs='git status'\n# END akash aliases\n",
+                ),
+            ),
+            (
+                "append without trailing newline",
+                "export A=1",
+                Ok(
+                    "export A=1\n\n# BEGIN akash aliases\nalias gs='git status'\n# END akash aliases\n",
+                ),
+            ),
+            (
+                "replace keeps surrounding whitespace",
+                "a\n\n\n# BEGIN akash aliases\nold\n# END akash aliases\n\n  b\n",
+                Ok(
+                    "a\n\n\n# BEGIN akash aliases\nalias gs='git status'\n# END akash aliases\n\n  b\n",
+                ),
+            ),
+            (
+                "replace at end of file without newline",
+                "a\n# BEGIN akash aliases\nold\n# END akash aliases",
+                Ok("a\n# BEGIN akash aliases\nalias gs='git status'\n# END akash aliases"),
+            ),
+            (
+                "empty block",
+                "# BEGIN akash aliases\n# END akash aliases\n",
+                Ok("# BEGIN akash aliases\nalias gs='git status'\n# END akash aliases\n"),
+            ),
+            (
+                "CRLF file stays CRLF",
+                "a\r\n# BEGIN akash aliases\r\nold\r\n# END akash aliases\r\nb\r\n",
+                Ok(
+                    "a\r\n# BEGIN akash aliases\r\nalias gs='git status'\r\n# END akash aliases\r\nb\r\n",
+                ),
+            ),
+            (
+                "CRLF append",
+                "a\r\n",
+                Ok(
+                    "a\r\n\r\n# BEGIN akash aliases\r\nalias gs='git status'\r\n# END akash aliases\r\n",
+                ),
+            ),
+            (
+                "indented markers and trailing spaces",
+                "  # BEGIN akash aliases  \nold\n\t# END akash aliases\nb\n",
+                Ok("# BEGIN akash aliases\nalias gs='git status'\n# END akash aliases\nb\n"),
+            ),
+            (
+                "marker text inside a command is not a marker",
+                "alias x='echo # END akash aliases'\n# BEGIN akash aliases\nold\n# END akash aliases\n",
+                Ok(
+                    "alias x='echo # END akash aliases'\n# BEGIN akash aliases\nalias gs='git status'\n# END akash aliases\n",
+                ),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn given_ambiguous_markers_when_replacing_then_refuses() {
+        check_cases(&[
+            (
+                "end before begin",
+                "# END akash aliases\nx\n# BEGIN akash aliases\n",
+                Err("on line 1 comes before"),
+            ),
+            (
+                "two begin markers",
+                "# BEGIN akash aliases\n# BEGIN akash aliases\n# END akash aliases\n",
+                Err("appears more than once (lines 1, 2)"),
+            ),
+            (
+                "two blocks",
+                "# BEGIN akash aliases\n# END akash aliases\n# BEGIN akash aliases\n# END akash aliases\n",
+                Err("appears more than once (lines 1, 3)"),
+            ),
+            (
+                "two end markers",
+                "# BEGIN akash aliases\n# END akash aliases\n# END akash aliases\n",
+                Err("'# END akash aliases' appears more than once"),
+            ),
+            (
+                "begin without end",
+                "a\n# BEGIN akash aliases\nalias x='y'\n",
+                Err("on line 2 has no matching '# END akash aliases'"),
+            ),
+            (
+                "end without begin",
+                "alias x='y'\n# END akash aliases\n",
+                Err("has no matching '# BEGIN akash aliases' before it"),
+            ),
+        ]);
+    }
+
+    #[test]
+    fn given_files_with_block_when_removing_then_restores_surroundings() {
+        let cases = [
+            // (case, input, expected)
+            (
+                "appended block",
+                "export A=1\n\n# BEGIN akash aliases\nx\n# END akash aliases\n",
+                "export A=1\n",
+            ),
+            (
+                "CRLF appended block",
+                "a\r\n\r\n# BEGIN akash aliases\r\n# END akash aliases\r\n",
+                "a\r\n",
+            ),
+            (
+                "block in the middle",
+                "a\n\n# BEGIN akash aliases\nx\n# END akash aliases\n\nb\n",
+                "a\n\n\nb\n",
+            ),
+            (
+                "only the block",
+                "# BEGIN akash aliases\nx\n# END akash aliases\n",
+                "",
+            ),
+            (
+                "no trailing newline",
+                "a\n# BEGIN akash aliases\n# END akash aliases",
+                "a\n",
+            ),
+        ];
+        for (case, input, expected) in cases {
+            let result = remove_block(input, BEGIN, END).unwrap();
+            assert_eq!(result.as_deref(), Some(expected), "case: {}", case);
+        }
+    }
+
+    #[test]
+    fn given_init_then_uninstall_when_removing_then_file_is_unchanged() {
+        // Given
+        let original = "export A=1\n";
+        let applied = replace_or_append_block(
+            original,
+            BEGIN,
+            END,
+            "# BEGIN akash aliases\n# END akash aliases",
+        )
+        .unwrap();
+
+        // When
+        let removed = remove_block(&applied, BEGIN, END).unwrap();
+
+        // Then
+        assert_eq!(removed.as_deref(), Some(original));
+        assert_eq!(remove_block(original, BEGIN, END).unwrap(), None);
+    }
+
+    #[test]
+    fn given_block_when_locating_then_lines_exclude_markers() {
+        // Given
+        let content = "export A=1\r\n# BEGIN akash aliases\r\nalias gs='git status'\r\n# END akash aliases\r\n";
+
+        // When
+        let span = locate_block(content, BEGIN, END).unwrap().unwrap();
+
+        // Then
+        assert_eq!(span.lines(content), vec!["alias gs='git status'"]);
+    }
+
+    #[test]
+    fn given_generated_block_when_splitting_then_matches_generated_entries() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias(String::from("gs"), String::from("git status"));
+        store.add_function(
+            String::from("mkcd"),
+            String::from("mkdir -p \"$1\"\ncd \"$1\""),
+            None,
+        );
+        let shell = get_shell(Some(ShellType::Bash)).unwrap();
+        let block = shell.generate_alias_block(&store);
+        let span = locate_block(&block, BEGIN, END).unwrap().unwrap();
+        let lines = span.lines(&block);
+
+        // When
+        let split = split_entries(shell.as_ref(), &lines);
+
+        // Then
+        let expected: BTreeMap<String, String> =
+            shell.generate_entries(&store).into_iter().collect();
+        assert_eq!(split.entries, expected);
+        assert!(split.unrecognized.is_empty());
+    }
+
+    #[test]
+    fn given_function_body_that_looks_like_definitions_when_splitting_then_keeps_it_whole() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias(String::from("gs"), String::from("git status"));
+        store.add_function(
+            String::from("setup"),
+            String::from("alias ll='ls -la'\nhelper() {\n  echo '}'\n}\nhelper"),
+            None,
+        );
+        store.add_function(
+            String::from("greet"),
+            String::from("if test -n \"$argv\"\nalias hi 'echo hi'\nend"),
+            Some("fish"),
+        );
+
+        for shell_type in [ShellType::Bash, ShellType::Fish] {
+            let shell = get_shell(Some(shell_type)).unwrap();
+            let block = shell.generate_alias_block(&store);
+            let span = locate_block(&block, &shell.begin_marker(), &shell.end_marker())
+                .unwrap()
+                .unwrap();
+            let lines = span.lines(&block);
+
+            // When
+            let split = split_entries(shell.as_ref(), &lines);
+
+            // Then
+            let expected: BTreeMap<String, String> =
+                shell.generate_entries(&store).into_iter().collect();
+            assert_eq!(split.entries, expected, "{}", shell.name());
+            assert!(split.unrecognized.is_empty());
+        }
+    }
+
+    #[test]
+    fn given_shadowed_powershell_alias_when_splitting_then_keeps_remove_line_with_it() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias(String::from("ls"), String::from("eza -la"));
+        let shell = get_shell(Some(ShellType::PowerShell)).unwrap();
+        let entries = shell.generate_entries(&store);
+        let lines: Vec<&str> = entries[0].1.lines().collect();
+
+        // When
+        let split = split_entries(shell.as_ref(), &lines);
+
+        // Then
+        assert_eq!(split.entries.len(), 1);
+        assert_eq!(split.entries["ls"], entries[0].1);
+    }
+}
@@ -1,5 +1,7 @@
+use crate::export::ExportFormat;
 use crate::shell::ShellType;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -8,7 +10,7 @@ use clap::{Parser, Subcommand};
     version
 )]
 pub struct Cli {
-    /// Override detected shell (bash, zsh, powershell)
+    /// Override detected shell (bash, zsh, fish, nushell, powershell, cmd, sh, ksh)
     #[arg(long, short, global = true)]
     pub shell: Option<ShellType>,
 
@@ -18,22 +20,151 @@ pub struct Cli {
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
-    /// Add a new alias
+    /// Add a new alias, or a multi-line function with --function
     Add {
         /// Alias name (e.g. "gs")
         name: String,
-        /// Command the alias expands to (e.g. "git status")
-        command: String,
+        /// Command the alias expands to (e.g. "git status").
+        /// Use {1}, {2}, ... for positional arguments and {@} for all of them
+        /// (e.g. "git checkout {1}")
+        #[arg(required_unless_present = "function")]
+        command: Option<String>,
+        /// Add a shell function; the body is read from stdin, or edited in $EDITOR
+        #[arg(long, conflicts_with = "command")]
+        function: bool,
+        /// Set the function body for one shell only (e.g. powershell)
+        #[arg(long = "for", value_name = "SHELL", requires = "function")]
+        for_shell: Option<ShellType>,
+        /// Why this alias exists
+        #[arg(long, short, conflicts_with = "function")]
+        description: Option<String>,
+        /// Tag the alias (repeatable, e.g. --tag git --tag vcs)
+        #[arg(long, short, conflicts_with = "function")]
+        tag: Vec<String>,
+        /// Add it even if it shadows a command and conflict_policy is "refuse",
+        /// or if it makes a cycle of aliases
+        #[arg(long)]
+        force: bool,
+        /// Refuse it on safety lint warnings too, not only on dangerous commands
+        #[arg(long)]
+        strict: bool,
     },
     /// Remove an existing alias
     Remove {
         /// Alias name to remove
         name: String,
+        /// Show the resulting shell config diff without changing anything
+        #[arg(long)]
+        dry_run: bool,
     },
     /// List all aliases
-    List,
+    List {
+        /// Only aliases with this tag (repeatable: all tags must match)
+        #[arg(long, short)]
+        tag: Vec<String>,
+        /// Only aliases created by this author
+        #[arg(long, short)]
+        author: Option<String>,
+        /// Only aliases whose name, command or description contains this text
+        #[arg(long, short = 'q')]
+        search: Option<String>,
+        /// Also show author and timestamps
+        #[arg(long, short)]
+        long: bool,
+    },
     /// Write aliases to your shell config file
-    Apply,
+    Apply {
+        /// Print a diff of the pending changes instead of writing (exits 1 if there are any)
+        #[arg(long)]
+        dry_run: bool,
+        /// Apply to every shell listed in `shells` in config.toml
+        #[arg(long)]
+        all: bool,
+        /// Apply even if aliases shadow commands and conflict_policy is "refuse"
+        #[arg(long)]
+        force: bool,
+        /// Refuse to apply on safety lint warnings too, not only on dangerous commands
+        #[arg(long)]
+        strict: bool,
+    },
     /// Configure shell to auto-load akash aliases on startup
-    Init,
+    Init {
+        /// Shell to set up (default: --shell, or the detected shell)
+        #[arg(value_name = "SHELL")]
+        target: Option<ShellType>,
+        /// Print a diff of the pending changes instead of writing (exits 1 if there are any)
+        #[arg(long)]
+        dry_run: bool,
+        /// Print the alias definitions to stdout instead, like `akash hook`
+        #[arg(long, conflicts_with = "dry_run")]
+        print: bool,
+        /// Apply even if aliases shadow commands and conflict_policy is "refuse"
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print alias definitions for the shell to evaluate at startup,
+    /// e.g. `eval "$(akash hook bash)"`
+    Hook {
+        /// Shell to generate for (default: --shell, or the detected shell)
+        #[arg(value_name = "SHELL")]
+        target: Option<ShellType>,
+    },
+    /// Check whether shell configs are in sync with the alias store (exits 1 on drift)
+    Status,
+    /// List aliases that shadow a command on PATH, a builtin or a keyword of the shell,
+    /// cycles of aliases, aliases that run through other aliases, and safety lint findings
+    /// (exits 1 on conflicts, cycles or findings)
+    Check,
+    /// Show how an alias expands, step by step, down to the command that runs
+    Expand {
+        /// Alias name
+        name: String,
+    },
+    /// Remove the akash block from every shell config
+    #[command(alias = "deinit")]
+    Uninstall {
+        /// Show what would be removed without changing anything (exits 1 if anything would be)
+        #[arg(long)]
+        dry_run: bool,
+        /// Also delete ~/.akash: aliases, config, backups and generated files
+        #[arg(long)]
+        purge: bool,
+    },
+    /// Import aliases already defined in a shell config, outside the akash block
+    Import {
+        /// File to read (an rc file, or saved `alias` / `Get-Alias` output), `-` for stdin,
+        /// a shell whose config to read (e.g. bash, powershell), or `oh-my-zsh` for its plugins
+        #[arg(long, value_name = "FILE|SHELL")]
+        from: String,
+        /// Replace aliases that already exist with a different command
+        #[arg(long)]
+        overwrite: bool,
+        /// Import without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Show what would be imported without changing anything
+        #[arg(long, conflicts_with = "yes")]
+        dry_run: bool,
+        /// Refuse to import on safety lint warnings too, not only on dangerous commands
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Write every alias and function as a script, data file or Markdown cheat sheet
+    Export {
+        /// bash, zsh, fish, pwsh, json, toml, yaml or markdown
+        /// (default: from the --output extension)
+        #[arg(long, short)]
+        format: Option<ExportFormat>,
+        /// File to write (default: stdout)
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Roll a shell config back to a backup taken before akash modified it
+    Restore {
+        /// Backup to restore (default: the latest backup of the current shell's config)
+        id: Option<String>,
+        /// List available backups instead of restoring
+        #[arg(long, conflicts_with = "id")]
+        list: bool,
+    },
 }
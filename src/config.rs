@@ -1,5 +1,7 @@
+use crate::shell::ShellType;
 use anyhow::{Context, Result};
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use tracing::debug;
 
@@ -8,24 +10,81 @@ pub struct Config {
     /// Override default shell detection
     pub shell: Option<String>,
 
+    /// Shells that `apply --all` writes to
+    #[serde(default)]
+    pub shells: Vec<String>,
+
     /// Custom path for the aliases JSON file
     pub aliases_path: Option<PathBuf>,
 
     /// Log level: error, warn, info, debug, trace
     #[serde(default = "default_log_level")]
     pub log_level: String,
+
+    /// How many backups to keep per shell config file (0 disables backups)
+    #[serde(default = "default_backup_retention")]
+    pub backup_retention: usize,
+
+    /// Where `apply` writes the aliases
+    #[serde(default)]
+    pub apply_mode: ApplyMode,
+
+    /// What `add` and `apply` do with aliases that shadow a command, builtin or keyword
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+
+    /// Safety lint rules turned off per alias: alias name -> rule names, or "all"
+    #[serde(default)]
+    pub lint_allow: BTreeMap<String, Vec<String>>,
+}
+
+/// How aliases reach the shell.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApplyMode {
+    /// The alias block is written into the shell config itself
+    #[default]
+    Inline,
+    /// The block goes to ~/.akash/generated/aliases.<ext>, which `init`
+    /// makes the shell config source once
+    Source,
+    /// Nothing is written: `init` makes the shell config evaluate the output
+    /// of `akash hook` at startup
+    Hook,
+}
+
+/// Reaction to an alias that shadows a command on PATH, a shell builtin or a keyword.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Print a warning and go on
+    #[default]
+    Warn,
+    /// Stop, unless --force is given
+    Refuse,
+    /// Check only with `akash check`
+    Ignore,
 }
 
 fn default_log_level() -> String {
     "error".to_string()
 }
 
+fn default_backup_retention() -> usize {
+    10
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             shell: None,
+            shells: Vec::new(),
             aliases_path: None,
             log_level: default_log_level(),
+            backup_retention: default_backup_retention(),
+            apply_mode: ApplyMode::default(),
+            conflict_policy: ConflictPolicy::default(),
+            lint_allow: BTreeMap::new(),
         }
     }
 }
@@ -46,11 +105,36 @@ impl Config {
 
         let default_content = r#"# Akash configuration file
 # Override default shell detection
-# Possible values: bash, zsh, powershell
+# Possible values: bash, zsh, fish, nushell, powershell, cmd, sh, ksh
 # shell = "powershell"
 
+# Shells updated by `akash apply --all`
+# shells = ["bash", "zsh"]
+
 # Log level: error, warn, info, debug, trace
 log_level = "warn"
+
+# Backups of each shell config kept in ~/.akash/backups (0 disables backups)
+# backup_retention = 10
+
+# "inline" writes aliases into your shell config on every apply.
+# "source" writes them to ~/.akash/generated/ and `akash init` adds one line
+# to your shell config that loads that file, so apply never edits your dotfiles.
+# "hook" makes your shell config run `akash hook` at startup (like
+# `eval "$(zoxide init bash)"`), so no apply is needed at all.
+# apply_mode = "inline"
+
+# Aliases that shadow a command on PATH, a shell builtin or a keyword (e.g. `cd`, `if`):
+# "warn" prints a warning, "refuse" stops `add` and `apply` unless --force is given,
+# "ignore" leaves it to `akash check`
+# conflict_policy = "warn"
+
+# The safety lint checks commands on add, import and apply: "danger" findings
+# (rm -rf /, curl | sh, ...) stop them, and warnings too with --strict.
+# Turn rules off for one alias by name, or all of them with "all". Rules:
+# destructive-rm, pipe-to-shell, sensitive-override, sudo-no-prompt, plain-credentials
+# [lint_allow]
+# nuke = ["destructive-rm"]
             "#;
 
         std::fs::write(&path, default_content)
@@ -84,6 +168,20 @@ log_level = "warn"
         Ok(config)
     }
 
+    /// Parse the `shells` list, without duplicates.
+    pub fn shell_types(&self) -> Result<Vec<ShellType>> {
+        let mut types = Vec::new();
+        for name in &self.shells {
+            let shell_type: ShellType = name
+                .parse()
+                .with_context(|| format!("Invalid entry '{}' in shells", name))?;
+            if !types.contains(&shell_type) {
+                types.push(shell_type);
+            }
+        }
+        Ok(types)
+    }
+
     /// Parse log_level string into tracing::Level
     pub fn tracing_level(&self) -> tracing::Level {
         match self.log_level.to_lowercase().as_str() {
@@ -95,3 +193,61 @@ log_level = "warn"
         }
     }
 }
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_shells_list_when_parsing_then_returns_unique_shell_types() {
+        // Given
+        let config: Config = toml::from_str(r#"shells = ["bash", "pwsh", "bash"]"#).unwrap();
+
+        // When
+        let shells = config.shell_types().unwrap();
+
+        // Then
+        assert_eq!(shells, vec![ShellType::Bash, ShellType::PowerShell]);
+    }
+
+    #[test]
+    fn given_conflict_policy_when_parsing_then_defaults_to_warn() {
+        // Given
+        let refuse = r#"conflict_policy = "refuse""#;
+
+        // When
+        let configured: Config = toml::from_str(refuse).unwrap();
+        let default: Config = toml::from_str("").unwrap();
+
+        // Then
+        assert_eq!(configured.conflict_policy, ConflictPolicy::Refuse);
+        assert_eq!(default.conflict_policy, ConflictPolicy::Warn);
+    }
+
+    #[test]
+    fn given_lint_allow_table_when_parsing_then_maps_aliases_to_rules() {
+        // Given
+        let content = "[lint_allow]\nnuke = [\"destructive-rm\"]\ninstall = [\"all\"]";
+
+        // When
+        let config: Config = toml::from_str(content).unwrap();
+
+        // Then
+        assert_eq!(config.lint_allow["nuke"], vec!["destructive-rm"]);
+        assert_eq!(config.lint_allow["install"], vec!["all"]);
+        assert!(Config::default().lint_allow.is_empty());
+    }
+
+    #[test]
+    fn given_unknown_shell_when_parsing_then_fails() {
+        // Given
+        let config: Config = toml::from_str(r#"shells = ["tcsh"]"#).unwrap();
+
+        // When
+        let result = config.shell_types();
+
+        // Then
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,277 @@
+use crate::shell::{Shell, ShellType};
+use crate::store::AliasStore;
+use std::path::{Path, PathBuf};
+
+/// What an alias name collides with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictKind {
+    /// Reserved word of the shell language (e.g. `if`)
+    Keyword,
+    /// Command built into the shell (e.g. `cd`), or a PowerShell built-in alias
+    Builtin,
+    /// First executable of that name on PATH
+    Executable(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub name: String,
+    pub kind: ConflictKind,
+}
+
+impl Conflict {
+    /// e.g. "shadows the Bash builtin", "shadows /usr/bin/gs"
+    pub fn describe(&self, shell: &dyn Shell) -> String {
+        match &self.kind {
+            ConflictKind::Keyword => {
+                format!("is a {} keyword: the alias may never run", shell.name())
+            }
+            ConflictKind::Builtin if shell.shell_type() == ShellType::PowerShell => {
+                String::from("shadows a PowerShell built-in alias")
+            }
+            ConflictKind::Builtin => format!("shadows the {} builtin", shell.name()),
+            ConflictKind::Executable(path) => format!("shadows {}", path.display()),
+        }
+    }
+}
+
+/// Looks up alias names among the keywords and builtins of a shell and the commands on PATH.
+pub struct ConflictChecker<'a> {
+    shell: &'a dyn Shell,
+    search_path: Vec<PathBuf>,
+}
+
+impl<'a> ConflictChecker<'a> {
+    /// Checker for `shell`, searching the directories of $PATH.
+    pub fn new(shell: &'a dyn Shell) -> Self {
+        let search_path = std::env::var_os("PATH")
+            .map(|path| std::env::split_paths(&path).collect())
+            .unwrap_or_default();
+        Self::with_search_path(shell, search_path)
+    }
+
+    pub fn with_search_path(shell: &'a dyn Shell, search_path: Vec<PathBuf>) -> Self {
+        Self { shell, search_path }
+    }
+
+    /// What `name` would shadow when defined as `definition`, the most serious first:
+    /// a keyword, then a builtin, then the executable the shell would have run.
+    /// A definition that calls the command it shadows (`alias ls='ls --color'`) wraps it
+    /// on purpose, so only keywords are reported for it.
+    pub fn check(&self, name: &str, definition: &str) -> Option<Conflict> {
+        // PowerShell and cmd.exe resolve names case-insensitively
+        let case_insensitive = matches!(
+            self.shell.shell_type(),
+            ShellType::PowerShell | ShellType::Cmd
+        );
+        let same = |other: &str| {
+            if case_insensitive {
+                other.eq_ignore_ascii_case(name)
+            } else {
+                other == name
+            }
+        };
+
+        let kind = if self.shell.keywords().iter().any(|keyword| same(keyword)) {
+            ConflictKind::Keyword
+        } else if calls_itself(definition, same) {
+            return None;
+        } else if self.shell.builtins().iter().any(|builtin| same(builtin)) {
+            ConflictKind::Builtin
+        } else {
+            ConflictKind::Executable(self.find_executable(name)?)
+        };
+        Some(Conflict {
+            name: name.to_string(),
+            kind,
+        })
+    }
+
+    /// Conflicts of every alias and function the shell would get from `store`, by name.
+    pub fn check_store(&self, store: &AliasStore) -> Vec<Conflict> {
+        let key = self.shell.shell_type().key();
+        let mut conflicts: Vec<Conflict> = store
+            .aliases
+            .iter()
+            .filter_map(|(name, entry)| self.check(name, &entry.command))
+            .chain(
+                store
+                    .functions
+                    .iter()
+                    .filter_map(|(name, function)| self.check(name, function.body_for(key)?)),
+            )
+            .collect();
+        conflicts.sort_by(|a, b| a.name.cmp(&b.name));
+        conflicts
+    }
+
+    fn find_executable(&self, name: &str) -> Option<PathBuf> {
+        self.search_path
+            .iter()
+            .find_map(|dir| executable_in(dir, name))
+    }
+}
+
+/// Whether a command of `definition` starts with the name itself,
+/// possibly behind `command`, `builtin` or a backslash that skip alias lookup.
+fn calls_itself(definition: &str, same: impl Fn(&str) -> bool) -> bool {
+    definition
+        .split(['\n', ';', '|', '&', '(', '{'])
+        .filter_map(|command| {
+            let mut words = command.split_whitespace();
+            let mut first = words.next()?;
+            while matches!(first, "command" | "builtin" | "exec" | "env" | "sudo") {
+                first = words.next()?;
+            }
+            Some(first.trim_start_matches('\\').to_string())
+        })
+        .any(|first| {
+            same(&first)
+                || Path::new(&first)
+                    .file_name()
+                    .and_then(|file| file.to_str())
+                    .is_some_and(|file| file.len() < first.len() && same(file))
+        })
+}
+
+#[cfg(unix)]
+fn executable_in(dir: &Path, name: &str) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join(name);
+    let metadata = std::fs::metadata(&path).ok()?;
+    (metadata.is_file() && metadata.permissions().mode() & 0o111 != 0).then_some(path)
+}
+
+#[cfg(not(unix))]
+fn executable_in(dir: &Path, name: &str) -> Option<PathBuf> {
+    // Windows runs `name` as name.exe, name.cmd, ... in the order of PATHEXT
+    let extensions =
+        std::env::var("PATHEXT").unwrap_or_else(|_| String::from(".COM;.EXE;.BAT;.CMD"));
+    extensions
+        .split(';')
+        .filter(|extension| !extension.is_empty())
+        .map(|extension| dir.join(format!("{}{}", name, extension.to_lowercase())))
+        .find(|path| path.is_file())
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell;
+
+    #[cfg(unix)]
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("akash-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn given_keyword_or_builtin_when_checking_then_reports_it() {
+        // Given
+        let bash = shell::get_shell(Some(ShellType::Bash)).unwrap();
+        let checker = ConflictChecker::with_search_path(bash.as_ref(), Vec::new());
+
+        // When
+        let keyword = checker.check("if", "echo if");
+        let builtin = checker.check("cd", "z");
+        let free = checker.check("gs", "git status");
+
+        // Then
+        assert_eq!(keyword.unwrap().kind, ConflictKind::Keyword);
+        assert_eq!(builtin.unwrap().kind, ConflictKind::Builtin);
+        assert_eq!(free, None);
+    }
+
+    #[test]
+    fn given_powershell_builtin_alias_when_checking_then_ignores_case() {
+        // Given
+        let powershell = shell::get_shell(Some(ShellType::PowerShell)).unwrap();
+        let checker = ConflictChecker::with_search_path(powershell.as_ref(), Vec::new());
+
+        // When
+        let conflict = checker.check("GP", "git push").unwrap();
+
+        // Then
+        assert_eq!(conflict.kind, ConflictKind::Builtin);
+        assert_eq!(
+            conflict.describe(powershell.as_ref()),
+            "shadows a PowerShell built-in alias"
+        );
+    }
+
+    #[test]
+    fn given_wrapper_of_same_command_when_checking_then_no_conflict() {
+        // Given
+        let bash = shell::get_shell(Some(ShellType::Bash)).unwrap();
+        let checker = ConflictChecker::with_search_path(bash.as_ref(), Vec::new());
+
+        // When
+        let wrappers = [
+            checker.check("echo", "echo -e"),
+            checker.check("cd", "builtin cd \"$@\" && ls"),
+            checker.check("type", "LC_ALL=C; \\type -a"),
+        ];
+
+        // Then
+        assert_eq!(wrappers, [None, None, None]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn given_executable_on_path_when_checking_then_reports_first_match() {
+        // Given
+        use std::os::unix::fs::PermissionsExt;
+        let dir = test_dir("conflict-path");
+        let (first, second) = (dir.join("a"), dir.join("b"));
+        for bin in [&first, &second] {
+            std::fs::create_dir_all(bin).unwrap();
+            let path = bin.join("gs");
+            std::fs::write(&path, "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        std::fs::write(first.join("notes"), "not executable").unwrap();
+        let bash = shell::get_shell(Some(ShellType::Bash)).unwrap();
+        let checker = ConflictChecker::with_search_path(bash.as_ref(), vec![first.clone(), second]);
+
+        // When
+        let shadowed = checker.check("gs", "git status");
+        let plain_file = checker.check("notes", "cat ~/notes.md");
+        let wrapper = checker.check("gs", "/usr/bin/gs -q");
+
+        // Then
+        assert_eq!(
+            shadowed.unwrap().kind,
+            ConflictKind::Executable(first.join("gs"))
+        );
+        assert_eq!(plain_file, None);
+        assert_eq!(wrapper, None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn given_store_when_checking_then_covers_aliases_and_functions() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias(String::from("time"), String::from("date"));
+        store.add_alias(String::from("gs"), String::from("git status"));
+        store.add_function(String::from("echo"), String::from("printf '%s'"), None);
+        store.add_function(
+            String::from("ps"),
+            String::from("Get-Process"),
+            Some("powershell"),
+        );
+        let bash = shell::get_shell(Some(ShellType::Bash)).unwrap();
+        let checker = ConflictChecker::with_search_path(bash.as_ref(), Vec::new());
+
+        // When
+        let conflicts = checker.check_store(&store);
+
+        // Then
+        let names: Vec<&str> = conflicts.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["echo", "time"]);
+    }
+}
@@ -0,0 +1,205 @@
+use colored::Colorize;
+
+/// Lines of context around each change, as in `diff -u`
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Unified diff between two texts, or None if they are identical.
+/// `label` names the file in the `---`/`+++` header.
+pub fn unified_diff(old: &str, new: &str, label: &str, color: bool) -> Option<String> {
+    if old == new {
+        return None;
+    }
+
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let lines = diff_lines(&old_lines, &new_lines);
+
+    let paint = |text: String, style: fn(&str) -> colored::ColoredString| {
+        if color {
+            style(&text).to_string()
+        } else {
+            text
+        }
+    };
+
+    let mut output = vec![
+        paint(format!("--- {}", label), |s| s.bold()),
+        paint(format!("+++ {} (proposed)", label), |s| s.bold()),
+    ];
+
+    for hunk in hunks(&lines) {
+        let (old_start, new_start) = line_numbers(&lines, hunk.start);
+        let slice = &lines[hunk.clone()];
+        let old_count = slice
+            .iter()
+            .filter(|l| !matches!(l, Line::Added(_)))
+            .count();
+        let new_count = slice
+            .iter()
+            .filter(|l| !matches!(l, Line::Removed(_)))
+            .count();
+
+        output.push(paint(
+            format!(
+                "@@ -{} +{} @@",
+                range(old_start, old_count),
+                range(new_start, new_count)
+            ),
+            |s| s.cyan(),
+        ));
+        for line in slice {
+            output.push(match line {
+                Line::Same(text) => format!(" {}", text),
+                Line::Removed(text) => paint(format!("-{}", text), |s| s.red()),
+                Line::Added(text) => paint(format!("+{}", text), |s| s.green()),
+            });
+        }
+    }
+
+    // Differences only in the trailing newline produce no line changes
+    if output.len() == 2 {
+        output.push(String::from(
+            "\\ Only the end of file (trailing newline) differs",
+        ));
+    }
+    Some(output.join("\n"))
+}
+
+/// Hunk range in `diff -u` notation: "start,count" (1-based; start is the line before when empty)
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start.saturating_sub(1)),
+        1 => format!("{}", start),
+        _ => format!("{},{}", start, count),
+    }
+}
+
+/// 1-based line numbers in the old and new text at position `index` of the diff.
+fn line_numbers(lines: &[Line], index: usize) -> (usize, usize) {
+    let before = &lines[..index];
+    let old = before
+        .iter()
+        .filter(|l| !matches!(l, Line::Added(_)))
+        .count();
+    let new = before
+        .iter()
+        .filter(|l| !matches!(l, Line::Removed(_)))
+        .count();
+    (old + 1, new + 1)
+}
+
+/// Group changed lines (plus context) into hunks, merging hunks whose context overlaps.
+fn hunks(lines: &[Line]) -> Vec<std::ops::Range<usize>> {
+    let mut result: Vec<std::ops::Range<usize>> = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if matches!(line, Line::Same(_)) {
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT);
+        let end = (index + CONTEXT + 1).min(lines.len());
+        match result.last_mut() {
+            Some(last) if start <= last.end => last.end = last.end.max(end),
+            _ => result.push(start..end),
+        }
+    }
+    result
+}
+
+/// Line diff based on the longest common subsequence.
+/// Shell configs differ in a single block, so the common prefix and suffix are
+/// stripped first and the quadratic part only sees the changed region.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    // lcs[i][j] = LCS length of old_mid[i..] and new_mid[j..]
+    let mut lcs = vec![vec![0usize; new_mid.len() + 1]; old_mid.len() + 1];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lcs[i][j] = if old_mid[i] == new_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines: Vec<Line> = old[..prefix].iter().map(|l| Line::Same(l)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() && j < new_mid.len() {
+        if old_mid[i] == new_mid[j] {
+            lines.push(Line::Same(old_mid[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(Line::Removed(old_mid[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new_mid[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old_mid[i..].iter().map(|l| Line::Removed(l)));
+    lines.extend(new_mid[j..].iter().map(|l| Line::Added(l)));
+    lines.extend(old[old.len() - suffix..].iter().map(|l| Line::Same(l)));
+    lines
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_identical_texts_when_diffing_then_returns_none() {
+        // Given / When / Then
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", ".bashrc", false), None);
+    }
+
+    #[test]
+    fn given_changed_line_when_diffing_then_hunk_has_context() {
+        // Given
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n";
+
+        // When
+        let diff = unified_diff(old, new, ".bashrc", false).unwrap();
+
+        // Then
+        assert_eq!(
+            diff,
+            "--- .bashrc\n+++ .bashrc (proposed)\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8"
+        );
+    }
+
+    #[test]
+    fn given_empty_file_when_diffing_then_everything_is_added() {
+        // Given
+        let new = "# BEGIN akash aliases\n# END akash aliases\n";
+
+        // When
+        let diff = unified_diff("", new, ".zshrc", false).unwrap();
+
+        // Then
+        assert_eq!(
+            diff,
+            "--- .zshrc\n+++ .zshrc (proposed)\n@@ -0,0 +1,2 @@\n+# BEGIN akash aliases\n+# END akash aliases"
+        );
+    }
+}
@@ -0,0 +1,479 @@
+use crate::shell::{self, ShellType};
+use crate::store::AliasStore;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::Path;
+
+/// Output format of `akash export`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+    Json,
+    Toml,
+    Yaml,
+    Markdown,
+}
+
+/// Enables: "pwsh".parse::<ExportFormat>() → Ok(ExportFormat::PowerShell)
+/// Used by: clap to parse --format
+impl std::str::FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        match input.to_lowercase().as_str() {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            "pwsh" | "powershell" | "ps1" => Ok(Self::PowerShell),
+            "json" => Ok(Self::Json),
+            "toml" => Ok(Self::Toml),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "markdown" | "md" => Ok(Self::Markdown),
+            _ => anyhow::bail!(
+                "Unsupported format: '{}'. Supported: bash, zsh, fish, pwsh, json, toml, yaml, markdown",
+                input
+            ),
+        }
+    }
+}
+
+impl ExportFormat {
+    /// Format matching the extension of `path` (e.g. aliases.toml → TOML).
+    pub fn for_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+
+    /// Shell whose syntax the script formats use
+    fn shell_type(&self) -> Option<ShellType> {
+        match self {
+            Self::Bash => Some(ShellType::Bash),
+            Self::Zsh => Some(ShellType::Zsh),
+            Self::Fish => Some(ShellType::Fish),
+            Self::PowerShell => Some(ShellType::PowerShell),
+            _ => None,
+        }
+    }
+}
+
+/// An export, and the functions left out because they have no body for the target shell.
+pub struct Export {
+    pub text: String,
+    pub skipped_functions: Vec<String>,
+}
+
+/// Serialize the whole store in `format`.
+pub fn export(store: &AliasStore, format: ExportFormat) -> Result<Export> {
+    if let Some(shell_type) = format.shell_type() {
+        return shell_script(store, shell_type);
+    }
+
+    let text = match format {
+        // Same layout as aliases.json, so it can be used as `aliases_path` as-is
+        ExportFormat::Json => {
+            serde_json::to_string_pretty(store).context("Failed to serialize aliases to JSON")?
+                + "\n"
+        }
+        ExportFormat::Toml => {
+            toml::to_string_pretty(store).context("Failed to serialize aliases to TOML")?
+        }
+        ExportFormat::Yaml => {
+            let value = serde_json::to_value(store).context("Failed to serialize aliases")?;
+            let mut text = String::new();
+            match &value {
+                Value::Object(map) => yaml_map(map, 0, &mut text),
+                other => text.push_str(&yaml_scalar(other)),
+            }
+            text
+        }
+        _ => markdown(store),
+    };
+    Ok(Export {
+        text,
+        skipped_functions: Vec::new(),
+    })
+}
+
+/// Script to source from a shell: the same definitions `apply` writes, with descriptions as comments.
+fn shell_script(store: &AliasStore, shell_type: ShellType) -> Result<Export> {
+    let shell = shell::get_shell(Some(shell_type))?;
+    let prefix = shell.comment_prefix();
+
+    let mut lines = vec![format!(
+        "{} Aliases exported by akash for {}: source this file from your shell config",
+        prefix,
+        shell.name()
+    )];
+    for (name, text) in shell.generate_entries(store) {
+        let description = store
+            .aliases
+            .get(&name)
+            .and_then(|entry| entry.description.as_deref());
+        if let Some(description) = description {
+            lines.push(format!("{} {}", prefix, description));
+        }
+        lines.push(text);
+    }
+
+    let key = shell_type.key();
+    let skipped_functions = store
+        .functions
+        .iter()
+        .filter(|(_, function)| function.body_for(key).is_none())
+        .map(|(name, _)| name.clone())
+        .collect();
+    Ok(Export {
+        text: lines.join("\n") + "\n",
+        skipped_functions,
+    })
+}
+
+// ============================================================================
+// MARKDOWN
+// ============================================================================
+
+/// Cheat sheet: a table of aliases, then each function in a code block.
+fn markdown(store: &AliasStore) -> String {
+    let mut text = String::from("# Aliases\n\n");
+
+    if store.aliases.is_empty() {
+        text.push_str("No aliases defined.\n");
+    } else {
+        text.push_str("| Alias | Command | Description | Tags |\n");
+        text.push_str("| --- | --- | --- | --- |\n");
+        for (name, entry) in &store.aliases {
+            text.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                table_cell(&code_span(name)),
+                table_cell(&code_span(&entry.command)),
+                table_cell(entry.description.as_deref().unwrap_or_default()),
+                table_cell(&entry.tags.join(", "))
+            ));
+        }
+    }
+
+    if !store.functions.is_empty() {
+        text.push_str("\n## Functions\n");
+        for (name, function) in &store.functions {
+            text.push_str(&format!("\n### {}\n", code_span(name)));
+            if !function.body.is_empty() {
+                text.push_str(&code_block(&function.body, "sh"));
+            }
+            for (key, body) in &function.variants {
+                text.push_str(&format!("\n{}:\n", key));
+                text.push_str(&code_block(body, key));
+            }
+        }
+    }
+    text
+}
+
+/// Inline code, with a longer fence when the text itself contains backticks.
+fn code_span(text: &str) -> String {
+    if text.contains('`') {
+        format!("`` {} ``", text)
+    } else {
+        format!("`{}`", text)
+    }
+}
+
+/// Pipes would end the cell and newlines the row, even inside code spans.
+fn table_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn code_block(body: &str, language: &str) -> String {
+    let fence = if body.contains("```") { "~~~" } else { "```" };
+    format!("\n{}{}\n{}\n{}\n", fence, language, body.trim_end(), fence)
+}
+
+// ============================================================================
+// YAML
+// ============================================================================
+
+/// Keys written first, in this order; JSON maps come back sorted alphabetically
+const YAML_KEY_ORDER: [&str; 7] = [
+    "version",
+    "aliases",
+    "functions",
+    "command",
+    "description",
+    "tags",
+    "body",
+];
+
+fn yaml_map(map: &serde_json::Map<String, Value>, indent: usize, text: &mut String) {
+    let padding = " ".repeat(indent);
+    let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+    entries.sort_by_key(|(key, _)| {
+        YAML_KEY_ORDER
+            .iter()
+            .position(|first| first == key)
+            .unwrap_or(YAML_KEY_ORDER.len())
+    });
+    for (key, value) in entries {
+        let key = yaml_string(key);
+        match value {
+            Value::Object(inner) if !inner.is_empty() => {
+                text.push_str(&format!("{}{}:\n", padding, key));
+                yaml_map(inner, indent + 2, text);
+            }
+            Value::Array(items) if !items.is_empty() => {
+                text.push_str(&format!("{}{}:\n", padding, key));
+                for item in items {
+                    text.push_str(&format!("{}  - {}\n", padding, yaml_scalar(item)));
+                }
+            }
+            Value::String(string) if block_literal(string) => {
+                // Function bodies stay readable as literal blocks; |- drops the final newline
+                let chomp = if string.ends_with('\n') { "" } else { "-" };
+                text.push_str(&format!("{}{}: |{}\n", padding, key, chomp));
+                for line in string.lines() {
+                    match line {
+                        "" => text.push('\n'),
+                        line => text.push_str(&format!("{}  {}\n", padding, line)),
+                    }
+                }
+            }
+            other => text.push_str(&format!("{}{}: {}\n", padding, key, yaml_scalar(other))),
+        }
+    }
+}
+
+/// Whether a multi-line string can be written as a literal block unchanged.
+fn block_literal(string: &str) -> bool {
+    string.contains('\n')
+        && !string.starts_with([' ', '\n'])
+        && !string.ends_with("\n\n")
+        && !string
+            .chars()
+            .any(|c| c.is_control() && c != '\n' && c != '\t')
+}
+
+/// Scalars, and empty or nested collections in JSON-style flow syntax (valid YAML).
+fn yaml_scalar(value: &Value) -> String {
+    match value {
+        Value::String(string) => yaml_string(string),
+        other => other.to_string(),
+    }
+}
+
+/// A plain scalar when YAML would read it back as the same string, double-quoted otherwise.
+fn yaml_string(string: &str) -> String {
+    let reserved = [
+        "true", "false", "yes", "no", "on", "off", "null", "y", "n", "~",
+    ];
+    let plain = !string.is_empty()
+        && string.starts_with(|c: char| c.is_alphanumeric() || "/._$".contains(c))
+        && !string.ends_with(' ')
+        && string
+            .chars()
+            .all(|c| c.is_alphanumeric() || " -_./$=+,()".contains(c))
+        && !reserved.contains(&string.to_lowercase().as_str())
+        && string.parse::<f64>().is_err();
+    if plain {
+        string.to_string()
+    } else {
+        // JSON escapes are a subset of YAML double-quoted escapes
+        Value::String(string.to_string()).to_string()
+    }
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_store() -> AliasStore {
+        let mut store = AliasStore::new_store();
+        store.add_alias(String::from("gs"), String::from("git status"));
+        store.add_alias(String::from("lg"), String::from("git log | less"));
+        let entry = store.alias_mut("gs").unwrap();
+        entry.description = Some(String::from("Short status"));
+        entry.tags = vec![String::from("git")];
+        for entry in store.aliases.values_mut() {
+            entry.created_at = None;
+            entry.updated_at = None;
+            entry.author = None;
+        }
+        store.add_function(
+            String::from("mkcd"),
+            String::from("mkdir -p \"$1\"\ncd \"$1\""),
+            None,
+        );
+        store
+    }
+
+    #[test]
+    fn given_format_names_when_parsing_then_accepts_aliases() {
+        // Given
+        let inputs = ["pwsh", "YAML", "yml", "md"];
+
+        // When
+        let formats: Vec<ExportFormat> = inputs.iter().map(|i| i.parse().unwrap()).collect();
+
+        // Then
+        assert_eq!(
+            formats,
+            vec![
+                ExportFormat::PowerShell,
+                ExportFormat::Yaml,
+                ExportFormat::Yaml,
+                ExportFormat::Markdown
+            ]
+        );
+        assert!("csv".parse::<ExportFormat>().is_err());
+        assert_eq!(
+            ExportFormat::for_path(Path::new("team/aliases.toml")),
+            Some(ExportFormat::Toml)
+        );
+    }
+
+    #[test]
+    fn given_store_when_exporting_bash_then_writes_definitions_with_descriptions() {
+        // Given
+        let store = sample_store();
+
+        // When
+        let export = export(&store, ExportFormat::Bash).unwrap();
+
+        // Then
+        let lines: Vec<&str> = export.text.lines().collect();
+        assert!(lines[0].starts_with("# Aliases exported by akash"));
+        assert_eq!(lines[1..3], ["# Short status", "alias gs='git status'"]);
+        assert!(export.text.contains("mkcd() {"));
+        assert!(export.skipped_functions.is_empty());
+    }
+
+    #[test]
+    fn given_function_without_variant_when_exporting_fish_then_reports_it() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_function(
+            String::from("f"),
+            String::from("Get-Date"),
+            Some("powershell"),
+        );
+
+        // When
+        let export = export(&store, ExportFormat::Fish).unwrap();
+
+        // Then
+        assert_eq!(export.skipped_functions, vec![String::from("f")]);
+    }
+
+    #[test]
+    fn given_store_when_exporting_toml_then_reads_back_the_same_aliases() {
+        // Given
+        let store = sample_store();
+
+        // When
+        let text = export(&store, ExportFormat::Toml).unwrap().text;
+        let parsed: AliasStore = toml::from_str(&text).unwrap();
+
+        // Then
+        assert_eq!(parsed.aliases, store.aliases);
+        assert_eq!(parsed.functions, store.functions);
+    }
+
+    #[test]
+    fn given_store_when_exporting_yaml_then_quotes_only_when_needed() {
+        // Given
+        let store = sample_store();
+
+        // When
+        let text = export(&store, ExportFormat::Yaml).unwrap().text;
+
+        // Then
+        assert_eq!(
+            text,
+            format!(
+                "version: {}\naliases:\n  gs:\n    command: git status\n    description: Short status\n    tags:\n      - git\n  lg:\n    command: \"git log | less\"\nfunctions:\n  mkcd:\n    body: |-\n      mkdir -p \"$1\"\n      cd \"$1\"\n",
+                store.version
+            )
+        );
+    }
+
+    #[test]
+    fn given_yaml_lookalikes_when_exporting_yaml_then_a_parser_reads_back_the_same_store() {
+        // Given
+        let mut store = sample_store();
+        let tricky = [
+            ("on", "*star"),
+            ("no", "&anchor"),
+            ("y", "!tag"),
+            ("off", "%directive"),
+            ("null", "@at"),
+            ("~", "`tick`"),
+            ("1e3", "0x1F"),
+            ("k: v", "- item"),
+            ("#c", "? key"),
+            ("[a]", "{b}"),
+            ("tilde", "~"),
+            ("trail", "echo 'a' "),
+            ("quotes", "say \"hi\" 'there'"),
+            ("colon", "echo a:b c: d"),
+            ("hash", "ls # comment"),
+            ("pipe", "| more"),
+            ("fold", "> out.txt"),
+            ("unicode", "echo café ✓"),
+            ("tab", "printf 'a\tb'"),
+        ];
+        for (name, command) in tricky {
+            store.add_alias(String::from(name), String::from(command));
+        }
+        store.add_function(
+            String::from("indented"),
+            String::from("if true; then\n    echo '# not a comment'\n\n    echo done\nfi\n"),
+            None,
+        );
+        store.add_function(String::from("lead"), String::from("  echo hi\n"), None);
+
+        // When
+        let text = export(&store, ExportFormat::Yaml).unwrap().text;
+        let parsed: AliasStore = serde_yaml_ng::from_str(&text).unwrap();
+
+        // Then
+        assert_eq!(parsed.version, store.version);
+        assert_eq!(parsed.aliases, store.aliases);
+        assert_eq!(parsed.functions, store.functions);
+    }
+
+    #[test]
+    fn given_yaml_lookalikes_when_quoting_then_keeps_them_strings() {
+        // Given
+        let inputs = ["yes", "1.5", "-x", "a: b", "tab\there", ""];
+
+        // When
+        let quoted: Vec<String> = inputs.iter().map(|i| yaml_string(i)).collect();
+
+        // Then
+        assert_eq!(
+            quoted,
+            vec![
+                "\"yes\"",
+                "\"1.5\"",
+                "\"-x\"",
+                "\"a: b\"",
+                "\"tab\\there\"",
+                "\"\""
+            ]
+        );
+    }
+
+    #[test]
+    fn given_store_when_exporting_markdown_then_escapes_table_cells() {
+        // Given
+        let store = sample_store();
+
+        // When
+        let text = export(&store, ExportFormat::Markdown).unwrap().text;
+
+        // Then
+        assert!(text.contains("| `gs` | `git status` | Short status | git |\n"));
+        assert!(text.contains("| `lg` | `git log \\| less` |  |  |\n"));
+        assert!(text.contains("### `mkcd`\n\n```sh\nmkdir -p \"$1\"\ncd \"$1\"\n```\n"));
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::{Context, Result};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, trace};
+
+/// Write `contents` to `path` without ever leaving a truncated file behind.
+///
+/// The data goes to a temporary file in the same directory, is flushed to disk,
+/// then renamed over the target, so readers see either the old or the new file.
+/// - If `path` is a symlink (e.g. `~/.bashrc` -> dotfiles repo), the link is kept
+///   and the file it points to is replaced.
+/// - The permissions (and ownership on Unix) of the existing file are preserved;
+///   the temporary file is created with them, so a private rc file is never readable
+///   by others, even briefly.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let target = resolve_symlinks(path)?;
+    if target != path {
+        debug!("{} is a symlink to {}", path.display(), target.display());
+    }
+
+    let parent = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file path: {}", target.display()))?;
+
+    let metadata = fs::metadata(&target).ok();
+    let (file, temp_path) = create_temp(&parent, file_name, metadata.as_ref())?;
+
+    let result = write_temp_then_rename(file, &target, &temp_path, contents, metadata.as_ref());
+    if result.is_err() {
+        // Never leave the temporary file behind on failure
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+
+    sync_dir(&parent);
+    trace!("Atomically wrote {}", target.display());
+    Ok(())
+}
+
+/// Create `.NAME.akash-tmp-PID` (or `-PID-N`) next to the target, with the target's mode on Unix.
+/// A name left behind by a crashed run is skipped rather than failing every later write.
+fn create_temp(
+    parent: &Path,
+    file_name: &std::ffi::OsStr,
+    metadata: Option<&fs::Metadata>,
+) -> Result<(fs::File, PathBuf)> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if let Some(metadata) = metadata {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(metadata.permissions().mode() & 0o7777);
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+
+    let mut attempt = 0;
+    loop {
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(file_name);
+        temp_name.push(format!(".akash-tmp-{}", std::process::id()));
+        if attempt > 0 {
+            temp_name.push(format!("-{}", attempt));
+        }
+        let temp_path = parent.join(temp_name);
+        match options.open(&temp_path) {
+            Ok(file) => return Ok((file, temp_path)),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists && attempt < 100 => {
+                debug!(
+                    "{} already exists, trying another name",
+                    temp_path.display()
+                );
+                attempt += 1;
+            }
+            Err(error) => {
+                return Err(error).with_context(|| {
+                    format!("Failed to create temporary file {}", temp_path.display())
+                });
+            }
+        }
+    }
+}
+
+fn write_temp_then_rename(
+    mut file: fs::File,
+    target: &Path,
+    temp_path: &Path,
+    contents: &[u8],
+    metadata: Option<&fs::Metadata>,
+) -> Result<()> {
+    // The umask may have dropped bits of the target's mode, and ownership is not set yet
+    if let Some(metadata) = metadata {
+        copy_permissions(metadata, temp_path)?;
+    }
+
+    file.write_all(contents)
+        .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+    file.sync_all()
+        .with_context(|| format!("Failed to flush {}", temp_path.display()))?;
+
+    fs::rename(temp_path, target).with_context(|| format!("Failed to replace {}", target.display()))
+}
+
+/// Give the temporary file the same mode (and owner on Unix) as the file it replaces.
+fn copy_permissions(metadata: &fs::Metadata, temp_path: &Path) -> Result<()> {
+    fs::set_permissions(temp_path, metadata.permissions())
+        .with_context(|| format!("Failed to set permissions on {}", temp_path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // Only root can give a file away; for a regular user this is a no-op on their own files
+        if let Err(error) =
+            std::os::unix::fs::chown(temp_path, Some(metadata.uid()), Some(metadata.gid()))
+        {
+            debug!("Could not preserve ownership: {}", error);
+        }
+    }
+    Ok(())
+}
+
+/// Follow a chain of symlinks to the real file, even if it does not exist yet.
+fn resolve_symlinks(path: &Path) -> Result<PathBuf> {
+    let mut current = path.to_path_buf();
+
+    // Same limit as Linux (ELOOP)
+    for _ in 0..40 {
+        match fs::symlink_metadata(&current) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&current)
+                    .with_context(|| format!("Failed to read symlink {}", current.display()))?;
+                current = match current.parent() {
+                    Some(parent) if link.is_relative() => parent.join(link),
+                    _ => link,
+                };
+            }
+            _ => return Ok(current),
+        }
+    }
+    anyhow::bail!("Too many levels of symbolic links: {}", path.display())
+}
+
+/// Persist the rename itself. Best effort: not supported on every platform.
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Ok(handle) = fs::File::open(dir) {
+        let _ = handle.sync_all();
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("akash-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn given_missing_file_when_writing_atomically_then_file_is_created() {
+        // Given
+        let dir = test_dir("atomic-new");
+        let path = dir.join("aliases.json");
+
+        // When
+        write_atomic(&path, b"{}").unwrap();
+
+        // Then
+        let content = fs::read_to_string(&path).unwrap();
+        let leftovers = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(content, "{}");
+        assert_eq!(leftovers, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn given_existing_file_when_writing_atomically_then_permissions_are_kept() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Given
+        let dir = test_dir("atomic-perms");
+        let path = dir.join(".bashrc");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        // When
+        write_atomic(&path, b"new").unwrap();
+
+        // Then
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(content, "new");
+        assert_eq!(mode, 0o600);
+    }
+
+    #[test]
+    fn given_stale_temporary_file_when_writing_atomically_then_write_succeeds() {
+        // Given
+        let dir = test_dir("atomic-stale");
+        let path = dir.join("aliases.json");
+        let stale = dir.join(format!(".aliases.json.akash-tmp-{}", std::process::id()));
+        fs::write(&stale, "left by a crash").unwrap();
+
+        // When
+        let result = write_atomic(&path, b"{}");
+
+        // Then
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(content, "{}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn given_symlinked_file_when_writing_atomically_then_link_is_kept_and_target_updated() {
+        // Given
+        let dir = test_dir("atomic-symlink");
+        let dotfiles = dir.join("dotfiles");
+        fs::create_dir_all(&dotfiles).unwrap();
+        fs::write(dotfiles.join("bashrc"), "old").unwrap();
+        let link = dir.join(".bashrc");
+        std::os::unix::fs::symlink("dotfiles/bashrc", &link).unwrap();
+
+        // When
+        write_atomic(&link, b"new").unwrap();
+
+        // Then
+        let is_symlink = fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink();
+        let target_content = fs::read_to_string(dotfiles.join("bashrc")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(is_symlink);
+        assert_eq!(target_content, "new");
+    }
+}
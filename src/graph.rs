@@ -0,0 +1,282 @@
+use crate::store::AliasStore;
+use std::collections::BTreeMap;
+
+/// How aliases lead to one another: an alias depends on the alias its command starts with,
+/// the only word shells look up as an alias again.
+pub struct AliasGraph<'a> {
+    commands: BTreeMap<&'a str, &'a str>,
+    store: &'a AliasStore,
+}
+
+/// One step of an expansion: the alias replaced and the command it leads to.
+#[derive(Debug, PartialEq)]
+pub struct Step {
+    pub alias: String,
+    pub command: String,
+}
+
+/// Why an expansion stops.
+#[derive(Debug, PartialEq)]
+pub enum ExpansionEnd {
+    /// The first word is not an alias: the shell runs it
+    Command,
+    /// The first word is the alias being expanded, which the shell does not expand again
+    /// (`alias ls='ls --color'`)
+    SelfReference,
+    /// The first word is a function of the store
+    Function,
+    /// The first word is an alias already expanded: the listed names form a loop
+    Cycle(Vec<String>),
+}
+
+impl<'a> AliasGraph<'a> {
+    pub fn new(store: &'a AliasStore) -> Self {
+        let commands = store
+            .aliases
+            .iter()
+            .map(|(name, entry)| (name.as_str(), entry.command.as_str()))
+            .collect();
+        Self { commands, store }
+    }
+
+    /// Alias that the command of `name` starts with, other than `name` itself.
+    pub fn dependency(&self, name: &str) -> Option<&'a str> {
+        let word = first_word(self.commands.get(name)?)?;
+        let (dependency, _) = self.commands.get_key_value(word)?;
+        (*dependency != name).then_some(*dependency)
+    }
+
+    /// Names from `name` to the command that finally runs, e.g. `[gs, g, git]`.
+    /// A cycle ends with the name it comes back to.
+    pub fn chain(&self, name: &str) -> Vec<String> {
+        let mut chain = vec![name.to_string()];
+        let mut current = name;
+        while let Some(next) = self.dependency(current) {
+            let looped = chain.iter().any(|seen| seen == next);
+            chain.push(next.to_string());
+            if looped {
+                return chain;
+            }
+            current = next;
+        }
+        // A self-reference (`alias ls='ls --color'`) runs the command of the same name
+        if let Some(word) = self.commands.get(current).and_then(|c| first_word(c))
+            && word != current
+        {
+            chain.push(word.to_string());
+        }
+        chain
+    }
+
+    /// Every loop of aliases, each listed once from its smallest name,
+    /// e.g. `[a, b]` for a -> b -> a.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles: Vec<Vec<String>> = Vec::new();
+        for name in self.commands.keys() {
+            let chain = self.chain(name);
+            let Some(last) = chain.last() else { continue };
+            // Only a chain that comes back to its start is the loop itself
+            if chain.len() > 1 && last == name {
+                let mut cycle = chain[..chain.len() - 1].to_vec();
+                let smallest = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+                cycle.rotate_left(smallest);
+                if !cycles.contains(&cycle) {
+                    cycles.push(cycle);
+                }
+            }
+        }
+        cycles
+    }
+
+    /// Cycle that `name` is part of, if any.
+    pub fn cycle_of(&self, name: &str) -> Option<Vec<String>> {
+        self.cycles()
+            .into_iter()
+            .find(|cycle| cycle.iter().any(|member| member == name))
+    }
+
+    /// Aliases that run through at least one other alias, with their chains.
+    /// Chains ending in a cycle are left to `cycles`.
+    pub fn chains(&self) -> Vec<Vec<String>> {
+        self.commands
+            .keys()
+            .filter(|name| self.dependency(name).is_some())
+            .map(|name| self.chain(name))
+            .filter(|chain| match chain.split_last() {
+                Some((last, before)) => !before.contains(last),
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Expand `name` the way the shell does: replace the first word with its alias
+    /// until it is a command. None if `name` is not an alias.
+    pub fn expand(&self, name: &str) -> Option<(Vec<Step>, ExpansionEnd)> {
+        let mut command = self.commands.get(name)?.to_string();
+        let mut steps = vec![Step {
+            alias: name.to_string(),
+            command: command.clone(),
+        }];
+
+        loop {
+            let Some(word) = first_word(&command).map(String::from) else {
+                return Some((steps, ExpansionEnd::Command));
+            };
+            if steps.last().is_some_and(|step| step.alias == word) {
+                return Some((steps, ExpansionEnd::SelfReference));
+            }
+            if steps.iter().any(|step| step.alias == word) {
+                let start = steps
+                    .iter()
+                    .position(|step| step.alias == word)
+                    .unwrap_or(0);
+                let cycle = steps[start..]
+                    .iter()
+                    .map(|step| step.alias.clone())
+                    .collect();
+                return Some((steps, ExpansionEnd::Cycle(cycle)));
+            }
+            if self.store.functions.contains_key(&word) {
+                return Some((steps, ExpansionEnd::Function));
+            }
+            let Some(next) = self.commands.get(word.as_str()) else {
+                return Some((steps, ExpansionEnd::Command));
+            };
+
+            let rest = command.trim_start()[word.len()..].to_string();
+            command = format!("{}{}", next, rest);
+            steps.push(Step {
+                alias: word,
+                command: command.clone(),
+            });
+        }
+    }
+}
+
+/// e.g. "a -> b -> a" for the cycle `[a, b]`
+pub fn describe_cycle(cycle: &[String]) -> String {
+    let mut names = cycle.to_vec();
+    names.extend(cycle.first().cloned());
+    names.join(" -> ")
+}
+
+/// First word of a command, as the shell looks it up among aliases.
+/// A quoted or backslash-escaped word is never expanded, so None is returned for it.
+pub fn first_word(command: &str) -> Option<&str> {
+    let command = command.trim_start();
+    if command.starts_with(['\\', '\'', '"']) {
+        return None;
+    }
+    let end = command
+        .find(|c: char| c.is_whitespace() || ";|&<>()".contains(c))
+        .unwrap_or(command.len());
+    (end > 0).then(|| &command[..end])
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(aliases: &[(&str, &str)]) -> AliasStore {
+        let mut store = AliasStore::new_store();
+        for (name, command) in aliases {
+            store.add_alias(name.to_string(), command.to_string());
+        }
+        store
+    }
+
+    #[test]
+    fn given_commands_when_taking_first_word_then_stops_at_operators() {
+        // Given
+        let commands = ["git status", "  ls|less", "\\ls", "'g' x", "cd;ls", ""];
+
+        // When
+        let words: Vec<Option<&str>> = commands.iter().map(|c| first_word(c)).collect();
+
+        // Then
+        assert_eq!(
+            words,
+            vec![Some("git"), Some("ls"), None, None, Some("cd"), None]
+        );
+    }
+
+    #[test]
+    fn given_chained_aliases_when_building_chain_then_follows_to_the_command() {
+        // Given
+        let store = store(&[("gs", "g status"), ("g", "git"), ("ls", "ls --color")]);
+        let graph = AliasGraph::new(&store);
+
+        // When
+        let chain = graph.chain("gs");
+        let self_reference = graph.chain("ls");
+
+        // Then
+        assert_eq!(chain, vec!["gs", "g", "git"]);
+        assert_eq!(self_reference, vec!["ls"]);
+        assert_eq!(graph.chains(), vec![vec!["gs", "g", "git"]]);
+        assert!(graph.cycles().is_empty());
+    }
+
+    #[test]
+    fn given_loop_when_finding_cycles_then_reports_it_once() {
+        // Given
+        let store = store(&[("b", "c -v"), ("c", "a"), ("a", "b"), ("d", "a")]);
+        let graph = AliasGraph::new(&store);
+
+        // When
+        let cycles = graph.cycles();
+
+        // Then
+        assert_eq!(cycles, vec![vec!["a", "b", "c"]]);
+        assert_eq!(
+            graph.cycle_of("c"),
+            Some(vec![
+                String::from("a"),
+                String::from("b"),
+                String::from("c")
+            ])
+        );
+        assert_eq!(graph.cycle_of("d"), None);
+        assert_eq!(graph.chain("d"), vec!["d", "a", "b", "c", "a"]);
+        assert!(graph.chains().is_empty());
+    }
+
+    #[test]
+    fn given_chain_when_expanding_then_keeps_the_arguments() {
+        // Given
+        let store = store(&[("gsl", "gs --long"), ("gs", "g status"), ("g", "git")]);
+        let graph = AliasGraph::new(&store);
+
+        // When
+        let (steps, end) = graph.expand("gsl").unwrap();
+
+        // Then
+        let commands: Vec<&str> = steps.iter().map(|step| step.command.as_str()).collect();
+        assert_eq!(
+            commands,
+            vec!["gs --long", "g status --long", "git status --long"]
+        );
+        assert_eq!(end, ExpansionEnd::Command);
+        assert!(graph.expand("missing").is_none());
+    }
+
+    #[test]
+    fn given_loop_or_self_reference_when_expanding_then_stops() {
+        // Given
+        let store = store(&[("a", "b x"), ("b", "a y"), ("ls", "ls -G")]);
+        let graph = AliasGraph::new(&store);
+
+        // When
+        let (loop_steps, loop_end) = graph.expand("a").unwrap();
+        let (_, self_end) = graph.expand("ls").unwrap();
+
+        // Then
+        assert_eq!(loop_steps.last().unwrap().command, "a y x");
+        assert_eq!(
+            loop_end,
+            ExpansionEnd::Cycle(vec![String::from("a"), String::from("b")])
+        );
+        assert_eq!(self_end, ExpansionEnd::SelfReference);
+    }
+}
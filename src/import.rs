@@ -0,0 +1,1224 @@
+use crate::block::BlockSpan;
+use crate::shell::ShellType;
+use crate::store::AliasStore;
+use anyhow::{Context, Result, bail};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Longest multi-line quoted value or function body followed, in lines
+const MAX_CONTINUATION_LINES: usize = 200;
+
+/// Syntax of the file being imported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Syntax {
+    /// bash, zsh, sh and ksh `alias` commands
+    Posix,
+    /// Output of `alias`: bash prints `alias name='...'`, zsh just `name='...'`
+    AliasOutput,
+    /// `Set-Alias` / `New-Alias` and functions
+    PowerShell,
+    /// `Get-Alias | Format-Table` output saved to a file
+    AliasTable,
+}
+
+impl Syntax {
+    pub fn for_shell(shell_type: ShellType) -> Result<Self> {
+        match shell_type {
+            ShellType::Bash | ShellType::Zsh | ShellType::Sh | ShellType::Ksh => Ok(Self::Posix),
+            ShellType::PowerShell => Ok(Self::PowerShell),
+            other => bail!("Importing from {} is not supported", other),
+        }
+    }
+
+    /// PowerShell for .ps1/.psm1 files, POSIX otherwise.
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("ps1") || ext.eq_ignore_ascii_case("psm1") => {
+                Self::PowerShell
+            }
+            _ => Self::Posix,
+        }
+    }
+
+    /// Recognize saved command output, which has no telltale extension;
+    /// anything else keeps the `fallback` syntax.
+    pub fn detect(content: &str, fallback: Self) -> Self {
+        if table_columns(content).is_some() {
+            Self::AliasTable
+        } else if fallback == Self::Posix && is_alias_output(content) {
+            Self::AliasOutput
+        } else {
+            fallback
+        }
+    }
+
+    /// Shell whose conventions (e.g. function variants) imported entries follow
+    pub fn shell_type(&self) -> ShellType {
+        match self {
+            Self::Posix | Self::AliasOutput => ShellType::Bash,
+            Self::PowerShell | Self::AliasTable => ShellType::PowerShell,
+        }
+    }
+}
+
+/// What an imported definition becomes in the store.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Definition {
+    /// A plain alias command
+    Alias(String),
+    /// A function body that cannot be expressed as an alias
+    Function(String),
+}
+
+/// A definition found in the imported file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Found {
+    /// 1-based line where the definition starts
+    pub line: usize,
+    pub name: String,
+    pub definition: Definition,
+    /// Caveat shown next to the entry (e.g. zsh global alias)
+    pub note: Option<&'static str>,
+}
+
+/// A line that looked like a definition but could not be imported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skipped {
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Parsed {
+    pub found: Vec<Found>,
+    pub skipped: Vec<Skipped>,
+}
+
+impl Parsed {
+    /// Record a definition. Invalid names are skipped; a name defined twice
+    /// keeps its last definition, as the shell would.
+    fn add(&mut self, found: Found, text: &str) {
+        if let Err(error) = AliasStore::validate_alias_name(&found.name) {
+            self.skip(found.line, text, &format!("'{}': {}", found.name, error));
+            return;
+        }
+        self.found.retain(|existing| existing.name != found.name);
+        self.found.push(found);
+    }
+
+    fn skip(&mut self, line: usize, text: &str, reason: &str) {
+        self.skipped.push(Skipped {
+            line,
+            text: text.trim().to_string(),
+            reason: reason.to_string(),
+        });
+    }
+}
+
+/// Replace the managed block with empty lines, so akash's own definitions are not
+/// imported back while line numbers still match the file.
+pub fn outside_block(content: &str, span: Option<&BlockSpan>) -> String {
+    match span {
+        Some(span) => {
+            let blank = "\n".repeat(content[span.outer.clone()].matches('\n').count());
+            format!(
+                "{}{}{}",
+                &content[..span.outer.start],
+                blank,
+                &content[span.outer.end..]
+            )
+        }
+        None => content.to_string(),
+    }
+}
+
+/// Find the alias definitions in a shell config.
+pub fn parse(content: &str, syntax: Syntax) -> Parsed {
+    let mut parsed = Parsed::default();
+    match syntax {
+        Syntax::Posix => parse_posix(content, &mut parsed),
+        Syntax::AliasOutput => parse_alias_output(content, &mut parsed),
+        Syntax::PowerShell => parse_powershell(content, &mut parsed),
+        Syntax::AliasTable => parse_alias_table(content, &mut parsed),
+    }
+    parsed
+}
+
+// ============================================================================
+// POSIX
+// ============================================================================
+
+fn parse_posix(content: &str, parsed: &mut Parsed) {
+    for command in posix_commands(content, false) {
+        match command.words {
+            Some(words) => parse_alias_words(&words[1..], command.line, command.text, parsed),
+            None => parsed.skip(command.line, command.text, "unterminated quote"),
+        }
+    }
+
+    // Only lines that start with `alias` are read: say so for the ones that hide it further on
+    for (index, line) in content.lines().enumerate() {
+        if has_nested_alias(line) {
+            parsed.skip(
+                index + 1,
+                line,
+                "alias inside a compound statement, such as an if, a loop or a && chain: add it by hand",
+            );
+        }
+    }
+    parsed.skipped.sort_by_key(|skipped| skipped.line);
+}
+
+/// An `alias` command that does not start its line, e.g. `[ -x eza ] && alias ls=eza`
+/// or `if ...; then alias x=y; fi`.
+fn has_nested_alias(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') || is_alias_command(trimmed) {
+        return false;
+    }
+    let code = trimmed.split(" #").next().unwrap_or_default();
+    code.match_indices("alias").any(|(index, _)| {
+        let before = code[..index].trim_end();
+        let followed = code[index + "alias".len()..].starts_with([' ', '\t']);
+        let keyword = ["then", "do", "else"].iter().any(|keyword| {
+            before
+                .strip_suffix(keyword)
+                .is_some_and(|rest| rest.is_empty() || rest.ends_with([' ', '\t', ';']))
+        });
+        followed && (before.ends_with([';', '&', '|', '{', '(']) || keyword)
+    })
+}
+
+fn parse_alias_output(content: &str, parsed: &mut Parsed) {
+    for command in posix_commands(content, true) {
+        match command.words {
+            Some(words) if is_alias_command(command.text.trim_start()) => {
+                parse_alias_words(&words[1..], command.line, command.text, parsed)
+            }
+            Some(words) if is_assignment(&words) => {
+                parse_alias_words(&words, command.line, command.text, parsed)
+            }
+            Some(_) => parsed.skip(command.line, command.text, "not an alias definition"),
+            None => parsed.skip(command.line, command.text, "unterminated quote"),
+        }
+    }
+}
+
+/// Whether every command is an alias definition, as in the output of `alias`.
+fn is_alias_output(content: &str) -> bool {
+    let commands = posix_commands(content, true);
+    !commands.is_empty()
+        && commands.iter().all(|command| match &command.words {
+            Some(words) => is_alias_command(command.text.trim_start()) || is_assignment(words),
+            None => false,
+        })
+}
+
+fn is_alias_command(text: &str) -> bool {
+    text.starts_with("alias ") || text.starts_with("alias\t")
+}
+
+/// A lone `name=value` word, the way zsh lists its aliases.
+fn is_assignment(words: &[String]) -> bool {
+    matches!(words, [word] if word.find('=').is_some_and(|index| index > 0))
+}
+
+/// A command of a POSIX file, with its quoted values possibly spanning several lines.
+struct PosixCommand<'a> {
+    /// 1-based line where the command starts
+    line: usize,
+    /// First line of the command
+    text: &'a str,
+    /// None if a quote is never closed
+    words: Option<Vec<String>>,
+}
+
+/// The `alias` commands of `content`, or with `every_line` all its non-comment lines.
+/// Only those lines are split into words, so an apostrophe elsewhere in an rc file
+/// (`echo don't`) cannot swallow the lines after it.
+fn posix_commands(content: &str, every_line: bool) -> Vec<PosixCommand<'_>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut commands = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let start = index;
+        let first = lines[index];
+        index += 1;
+
+        let trimmed = first.trim_start();
+        let wanted = if every_line {
+            !trimmed.is_empty() && !trimmed.starts_with('#')
+        } else {
+            is_alias_command(trimmed)
+        };
+        if !wanted {
+            continue;
+        }
+
+        // A quoted value may span several lines
+        let mut text = trimmed.to_string();
+        let words = loop {
+            match posix_words(&text) {
+                Some(words) => break Some(words),
+                None if index < lines.len() && index - start < MAX_CONTINUATION_LINES => {
+                    text.push('\n');
+                    text.push_str(lines[index]);
+                    index += 1;
+                }
+                None => break None,
+            }
+        };
+
+        commands.push(PosixCommand {
+            line: start + 1,
+            text: first,
+            words,
+        });
+    }
+    commands
+}
+
+/// Arguments of one `alias` command: options, then `name=value` words.
+fn parse_alias_words(words: &[String], line: usize, text: &str, parsed: &mut Parsed) {
+    let mut global = false;
+    let mut rest = words;
+
+    while let Some(option) = rest.first().filter(|word| word.starts_with('-')) {
+        match option.as_str() {
+            "--" => {
+                rest = &rest[1..];
+                break;
+            }
+            "-g" => global = true,
+            "-s" => return parsed.skip(line, text, "zsh suffix aliases are not supported"),
+            // -p and -L only print aliases
+            "-p" | "-L" => return,
+            _ => return parsed.skip(line, text, &format!("unsupported option {}", option)),
+        }
+        rest = &rest[1..];
+    }
+
+    for word in rest {
+        // `alias name` without a value prints an alias, it defines nothing
+        if let Some((name, command)) = word.split_once('=') {
+            let found = Found {
+                line,
+                name: name.to_string(),
+                definition: Definition::Alias(command.to_string()),
+                note: global.then_some("zsh global alias, imported as a regular alias"),
+            };
+            parsed.add(found, text);
+        }
+    }
+}
+
+/// Split a command line into words following POSIX quoting: '...', "...", $'...'
+/// and backslash escapes. Stops at a comment or a command separator.
+/// Returns None if a quote is unterminated or the line ends with a backslash.
+fn posix_words(text: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '#' if !in_word => break,
+            ';' | '&' | '|' | '\n' => break,
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(next) => {
+                    word.push(next);
+                    in_word = true;
+                }
+                None => return None,
+            },
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        ch => word.push(ch),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        // Inside double quotes, backslash only escapes $ ` " \ and newline
+                        '\\' => match chars.next()? {
+                            '\n' => {}
+                            ch @ ('$' | '`' | '"' | '\\') => word.push(ch),
+                            ch => {
+                                word.push('\\');
+                                word.push(ch);
+                            }
+                        },
+                        ch => word.push(ch),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_word = true;
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        '\\' => match chars.next()? {
+                            'n' => word.push('\n'),
+                            't' => word.push('\t'),
+                            'r' => word.push('\r'),
+                            'e' | 'E' => word.push('\x1b'),
+                            'a' => word.push('\x07'),
+                            ch @ ('\\' | '\'' | '"') => word.push(ch),
+                            ch => {
+                                word.push('\\');
+                                word.push(ch);
+                            }
+                        },
+                        ch => word.push(ch),
+                    }
+                }
+            }
+            _ => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+    Some(words)
+}
+
+// ============================================================================
+// POWERSHELL
+// ============================================================================
+
+fn parse_powershell(content: &str, parsed: &mut Parsed) {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+        let trimmed = line.trim_start();
+        let number = index + 1;
+        index += 1;
+
+        let command = trimmed
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match command.as_str() {
+            "set-alias" | "new-alias" | "sal" | "nal" => parse_set_alias(trimmed, number, parsed),
+            "function" => {
+                // The body can span lines: continue after its closing brace
+                let rest = lines[number - 1..].join("\n");
+                let consumed = parse_function(&rest, number, parsed);
+                index = number - 1 + consumed.max(1);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `Set-Alias [-Name] name [-Value] value [-Scope s] [-Option o] [-Description d] [-Force]`
+fn parse_set_alias(text: &str, line: usize, parsed: &mut Parsed) {
+    let Some(words) = powershell_words(text) else {
+        return parsed.skip(line, text, "unterminated quote");
+    };
+
+    let mut name = None;
+    let mut value = None;
+    let mut positional = Vec::new();
+    let mut rest = words[1..].iter();
+
+    while let Some(word) = rest.next() {
+        let parameter = word
+            .strip_prefix('-')
+            .filter(|p| p.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .map(|p| p.trim_end_matches(':').to_lowercase());
+        match parameter {
+            // Parameter names can be abbreviated
+            Some(p) if "name".starts_with(&p) => name = rest.next(),
+            Some(p) if "value".starts_with(&p) => value = rest.next(),
+            Some(p)
+                if ["scope", "option", "description"]
+                    .iter()
+                    .any(|o| o.starts_with(&p)) =>
+            {
+                rest.next();
+            }
+            // Switches such as -Force or -PassThru
+            Some(_) => {}
+            None => positional.push(word),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let name = name.or_else(|| positional.next());
+    let value = value.or_else(|| positional.next());
+    match (name, value) {
+        (Some(name), Some(value)) => parsed.add(
+            Found {
+                line,
+                name: name.clone(),
+                definition: Definition::Alias(value.clone()),
+                note: None,
+            },
+            text,
+        ),
+        _ => parsed.skip(line, text, "Set-Alias without a name and a value"),
+    }
+}
+
+/// `function [scope:]name [(params)] { body }` at the start of `text`.
+/// Returns how many lines the definition spans.
+fn parse_function(text: &str, line: usize, parsed: &mut Parsed) -> usize {
+    let first_line = text.lines().next().unwrap_or_default();
+    let rest = text.trim_start()["function".len()..].trim_start();
+    let name_end = rest
+        .find(|c: char| c.is_whitespace() || c == '{' || c == '(')
+        .unwrap_or(rest.len());
+    let qualified = &rest[..name_end];
+    let name = match qualified.split_once(':') {
+        Some((scope, name))
+            if ["global", "script", "local"].contains(&scope.to_lowercase().as_str()) =>
+        {
+            name
+        }
+        _ => qualified,
+    };
+    let has_parameters = rest[name_end..].trim_start().starts_with('(');
+
+    let Some((body, consumed)) = braced_body(text) else {
+        parsed.skip(line, first_line, "function body without matching braces");
+        return 1;
+    };
+
+    let body = dedent(body.trim_matches('\n').trim_end());
+    let lower = body.to_lowercase();
+    let forwarded = body
+        .strip_suffix(" @args")
+        .or_else(|| body.strip_suffix(" $args"))
+        .unwrap_or(&body);
+
+    // A one-line body that at most forwards its arguments is an alias
+    let definition = if has_parameters
+        || body.contains('\n')
+        || lower.contains("param(")
+        || forwarded.contains("$args")
+        || forwarded.contains("@args")
+        || forwarded.contains("$input")
+    {
+        Definition::Function(body.clone())
+    } else {
+        Definition::Alias(forwarded.trim().to_string())
+    };
+
+    parsed.add(
+        Found {
+            line,
+            name: name.to_string(),
+            definition,
+            note: None,
+        },
+        first_line,
+    );
+    consumed
+}
+
+/// Text between the first `{` of `text` and its matching `}`, and the number of
+/// lines up to the closing brace. Braces inside quotes and comments are ignored.
+fn braced_body(text: &str) -> Option<(&str, usize)> {
+    let open = text.find('{')?;
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut in_comment = false;
+    let mut chars = text[open..].char_indices();
+
+    while let Some((offset, c)) = chars.next() {
+        let position = open + offset;
+        if text[..position].matches('\n').count() >= MAX_CONTINUATION_LINES {
+            return None;
+        }
+        if in_comment {
+            in_comment = c != '\n';
+            continue;
+        }
+        match (quote, c) {
+            (Some('"'), '`') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '#') => in_comment = true,
+            (None, '{') => depth += 1,
+            (None, '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    let lines = text[..position].matches('\n').count() + 1;
+                    return Some((&text[open + 1..position], lines));
+                }
+            }
+            (None, _) => {}
+        }
+    }
+    None
+}
+
+/// Split a PowerShell command into words: '...' ('' escapes a quote), "..."
+/// (backtick escapes) and bare words. Stops at a comment, `;` or `|`.
+fn powershell_words(text: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '#' if !in_word => break,
+            ';' | '|' => break,
+            '`' => {
+                word.push(chars.next()?);
+                in_word = true;
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next()? {
+                        '\'' if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                            word.push('\'');
+                        }
+                        '\'' => break,
+                        ch => word.push(ch),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next()? {
+                        '"' if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            word.push('"');
+                        }
+                        '"' => break,
+                        '`' => match chars.next()? {
+                            'n' => word.push('\n'),
+                            't' => word.push('\t'),
+                            ch => word.push(ch),
+                        },
+                        ch => word.push(ch),
+                    }
+                }
+            }
+            _ => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+    Some(words)
+}
+
+// ============================================================================
+// GET-ALIAS TABLE
+// ============================================================================
+
+/// Column of a Format-Table output: its header and the character range below it
+struct Column {
+    header: String,
+    start: usize,
+    end: Option<usize>,
+}
+
+impl Column {
+    fn cell(&self, line: &[char]) -> String {
+        let end = self.end.unwrap_or(line.len()).min(line.len());
+        line.get(self.start..end)
+            .map(|cell| cell.iter().collect::<String>().trim().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Columns of the first table with a `Name` column, and the index of its first row.
+/// Format-Table underlines each header with dashes, which gives the column widths.
+fn table_columns(content: &str) -> Option<(Vec<Column>, usize)> {
+    let lines: Vec<&str> = content.lines().collect();
+
+    for (index, pair) in lines.windows(2).enumerate() {
+        let (header, rule) = (pair[0], pair[1]);
+        if rule.trim().is_empty() || !rule.chars().all(|c| c == '-' || c == ' ') {
+            continue;
+        }
+
+        let rule: Vec<char> = rule.chars().collect();
+        let header: Vec<char> = header.chars().collect();
+        let starts: Vec<usize> = (0..rule.len())
+            .filter(|&i| rule[i] == '-' && (i == 0 || rule[i - 1] == ' '))
+            .collect();
+        let columns: Vec<Column> = starts
+            .iter()
+            .enumerate()
+            .map(|(n, &start)| {
+                let end = starts.get(n + 1).copied();
+                let mut column = Column {
+                    header: String::new(),
+                    start,
+                    end,
+                };
+                column.header = column.cell(&header);
+                column
+            })
+            .collect();
+
+        if columns.iter().any(|column| column.header == "Name") {
+            return Some((columns, index + 2));
+        }
+    }
+    None
+}
+
+/// `Get-Alias` shows `name -> definition` in its Name column;
+/// `Get-Alias | Format-Table Name, Definition` has a column of its own.
+fn parse_alias_table(content: &str, parsed: &mut Parsed) {
+    let Some((columns, first_row)) = table_columns(content) else {
+        return;
+    };
+    let column = |header: &str| columns.iter().find(|column| column.header == header);
+    let (Some(name_column), definition_column) = (column("Name"), column("Definition")) else {
+        return;
+    };
+
+    for (index, text) in content.lines().enumerate().skip(first_row) {
+        // The table ends at the first blank line
+        if text.trim().is_empty() {
+            break;
+        }
+        let line: Vec<char> = text.chars().collect();
+        let name_cell = name_column.cell(&line);
+        let (name, value) = match name_cell.split_once(" -> ") {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => (
+                name_cell.clone(),
+                definition_column
+                    .map(|column| column.cell(&line))
+                    .unwrap_or_default(),
+            ),
+        };
+
+        if name_cell.ends_with('…') || name_cell.ends_with("...") || value.ends_with('…') {
+            parsed.skip(
+                index + 1,
+                text,
+                "cut short by Format-Table (save the output of Format-Table -AutoSize)",
+            );
+        } else if value.is_empty() {
+            parsed.skip(index + 1, text, "no definition in the table");
+        } else {
+            parsed.add(
+                Found {
+                    line: index + 1,
+                    name,
+                    definition: Definition::Alias(value),
+                    note: None,
+                },
+                text,
+            );
+        }
+    }
+}
+
+// ============================================================================
+// OH-MY-ZSH
+// ============================================================================
+
+/// Plugins listed in `plugins=(...)` of a .zshrc, if it has that line.
+pub fn enabled_plugins(zshrc: &str) -> Option<Vec<String>> {
+    let start = zshrc
+        .lines()
+        .scan(0, |offset, line| {
+            let line_start = *offset;
+            *offset += line.len() + 1;
+            Some((line_start, line))
+        })
+        .find(|(_, line)| line.trim_start().starts_with("plugins=("))
+        .map(|(offset, line)| offset + line.find("plugins=(").unwrap_or(0) + "plugins=(".len())?;
+
+    let list = &zshrc[start..];
+    let list = &list[..list.find(')').unwrap_or(list.len())];
+    Some(
+        list.lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(str::split_whitespace)
+            .map(String::from)
+            .collect(),
+    )
+}
+
+/// `plugins/*/*.plugin.zsh` files of an oh-my-zsh checkout.
+/// For the oh-my-zsh directory itself, custom plugins replace bundled ones of the same
+/// name, and only `enabled` plugins are kept when given. Any other directory is read
+/// as a plugins directory: every plugin in it, or its own `*.plugin.zsh` files.
+pub fn plugin_files(dir: &Path, enabled: Option<&[String]>) -> Result<Vec<PathBuf>> {
+    let plugins = dir.join("plugins");
+    let (roots, enabled) = if plugins.is_dir() {
+        (vec![plugins, dir.join("custom").join("plugins")], enabled)
+    } else {
+        (vec![dir.to_path_buf()], None)
+    };
+
+    let mut files = BTreeMap::new();
+    for root in roots.iter().filter(|root| root.is_dir()) {
+        for file in plugin_files_in(root)? {
+            let name = file
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".plugin.zsh"))
+                .unwrap_or_default()
+                .to_string();
+            files.insert(name, file);
+        }
+    }
+
+    Ok(match enabled {
+        // Listed order, which is also the order oh-my-zsh loads them in
+        Some(enabled) => enabled
+            .iter()
+            .filter_map(|name| files.get(name).cloned())
+            .collect(),
+        None => files.into_values().collect(),
+    })
+}
+
+/// `*.plugin.zsh` files directly in `dir` or one level below.
+fn plugin_files_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let is_plugin = |path: &Path| {
+        path.is_file()
+            && path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(".plugin.zsh"))
+    };
+    let read = |dir: &Path| -> Result<Vec<PathBuf>> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        paths.sort();
+        Ok(paths)
+    };
+
+    let mut files = Vec::new();
+    for path in read(dir)? {
+        if path.is_dir() {
+            files.extend(read(&path)?.into_iter().filter(|path| is_plugin(path)));
+        } else if is_plugin(&path) {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Remove the indentation shared by every non-empty line.
+fn dedent(text: &str) -> String {
+    let indent = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    text.lines()
+        .map(|line| line.get(indent..).unwrap_or(line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(parsed: &Parsed) -> Vec<(&str, &Definition)> {
+        parsed
+            .found
+            .iter()
+            .map(|found| (found.name.as_str(), &found.definition))
+            .collect()
+    }
+
+    fn alias(command: &str) -> Definition {
+        Definition::Alias(command.to_string())
+    }
+
+    // --- POSIX ---
+
+    #[test]
+    fn given_bash_quoting_when_parsing_then_unquotes_like_bash() {
+        // Given
+        let content = r#"
+alias gs='git status'
+alias ll="ls -la"   # long listing
+alias say='echo '\''hi'\'''
+alias home=cd\ ~
+alias path="echo \"\$PATH\""
+alias tab=$'printf \'a\tb\''
+  alias a='x' b="y"
+alias ll
+"#;
+
+        // When
+        let parsed = parse(content, Syntax::Posix);
+
+        // Then
+        assert_eq!(
+            aliases(&parsed),
+            vec![
+                ("gs", &alias("git status")),
+                ("ll", &alias("ls -la")),
+                ("say", &alias("echo 'hi'")),
+                ("home", &alias("cd ~")),
+                ("path", &alias("echo \"$PATH\"")),
+                ("tab", &alias("printf 'a\tb'")),
+                ("a", &alias("x")),
+                ("b", &alias("y")),
+            ]
+        );
+        assert!(parsed.skipped.is_empty());
+    }
+
+    #[test]
+    fn given_multiline_value_when_parsing_then_joins_lines() {
+        // Given
+        let content = "alias multi='echo one\necho two'\nalias next='x'\n";
+
+        // When
+        let parsed = parse(content, Syntax::Posix);
+
+        // Then
+        assert_eq!(
+            aliases(&parsed),
+            vec![
+                ("multi", &alias("echo one\necho two")),
+                ("next", &alias("x"))
+            ]
+        );
+        assert_eq!(parsed.found[1].line, 3);
+    }
+
+    #[test]
+    fn given_zsh_options_when_parsing_then_handles_global_and_skips_suffix() {
+        // Given
+        let content = "alias -g G='| grep'\nalias -s txt=vim\nalias -- a.b='y'\n";
+
+        // When
+        let parsed = parse(content, Syntax::Posix);
+
+        // Then
+        assert_eq!(aliases(&parsed), vec![("G", &alias("| grep"))]);
+        assert!(parsed.found[0].note.is_some());
+        assert_eq!(parsed.skipped.len(), 2);
+        assert_eq!(parsed.skipped[0].line, 2);
+        assert!(parsed.skipped[1].reason.contains("'a.b'"));
+    }
+
+    #[test]
+    fn given_unterminated_quote_when_parsing_then_skips_line() {
+        // Given
+        let content = "alias broken='oops\n";
+
+        // When
+        let parsed = parse(content, Syntax::Posix);
+
+        // Then
+        assert!(parsed.found.is_empty());
+        assert_eq!(parsed.skipped[0].reason, "unterminated quote");
+    }
+
+    #[test]
+    fn given_alias_inside_compound_statement_when_parsing_then_reports_it_as_skipped() {
+        // Given
+        let content = "\
+if [ -x /usr/bin/dircolors ]; then alias ls='ls --color=auto'; fi
+command -v eza >/dev/null && alias ll='eza -l'
+alias gs='git status'
+unalias gd 2>/dev/null; echo 'no alias here'
+";
+
+        // When
+        let parsed = parse(content, Syntax::Posix);
+
+        // Then
+        assert_eq!(aliases(&parsed), vec![("gs", &alias("git status"))]);
+        let lines: Vec<usize> = parsed.skipped.iter().map(|skipped| skipped.line).collect();
+        assert_eq!(lines, vec![1, 2]);
+        assert!(parsed.skipped[0].reason.contains("compound statement"));
+    }
+
+    // --- PowerShell ---
+
+    #[test]
+    fn given_set_alias_forms_when_parsing_then_reads_name_and_value() {
+        // Given
+        let content = r#"
+Set-Alias -Name g -Value git
+Set-Alias ll Get-ChildItem -Scope Global -Force
+New-Alias -Value 'code' -N c
+sal -Option AllScope np notepad.exe
+Set-Alias -Name "v" -Value "C:\Program Files\vim.exe"
+"#;
+
+        // When
+        let parsed = parse(content, Syntax::PowerShell);
+
+        // Then
+        assert_eq!(
+            aliases(&parsed),
+            vec![
+                ("g", &alias("git")),
+                ("ll", &alias("Get-ChildItem")),
+                ("c", &alias("code")),
+                ("np", &alias("notepad.exe")),
+                ("v", &alias(r"C:\Program Files\vim.exe")),
+            ]
+        );
+    }
+
+    #[test]
+    fn given_powershell_functions_when_parsing_then_simple_ones_become_aliases() {
+        // Given
+        let content = r#"function gs { git status @args }
+function global:gp { git push }
+function mkcd {
+    param($path)
+    New-Item -ItemType Directory $path | Out-Null
+    Set-Location $path
+}
+function greet($name) { "Hello {0}" -f $name }
+Set-Alias -Name after -Value x
+"#;
+
+        // When
+        let parsed = parse(content, Syntax::PowerShell);
+
+        // Then
+        assert_eq!(
+            aliases(&parsed),
+            vec![
+                ("gs", &alias("git status")),
+                ("gp", &alias("git push")),
+                (
+                    "mkcd",
+                    &Definition::Function(String::from(
+                        "param($path)\nNew-Item -ItemType Directory $path | Out-Null\nSet-Location $path"
+                    ))
+                ),
+                (
+                    "greet",
+                    &Definition::Function(String::from("\"Hello {0}\" -f $name"))
+                ),
+                ("after", &alias("x")),
+            ]
+        );
+        assert_eq!(parsed.found[4].line, 9);
+    }
+
+    #[test]
+    fn given_managed_block_when_importing_then_block_is_blanked() {
+        // Given
+        let content =
+            "alias a='1'\n# BEGIN akash aliases\nalias b='2'\n# END akash aliases\nalias c='3'\n";
+        let span =
+            crate::block::locate_block(content, "# BEGIN akash aliases", "# END akash aliases")
+                .unwrap();
+
+        // When
+        let parsed = parse(&outside_block(content, span.as_ref()), Syntax::Posix);
+
+        // Then
+        assert_eq!(
+            aliases(&parsed),
+            vec![("a", &alias("1")), ("c", &alias("3"))]
+        );
+        assert_eq!(parsed.found[1].line, 5);
+    }
+
+    // --- alias output ---
+
+    #[test]
+    fn given_zsh_alias_output_when_detecting_then_parses_bare_assignments() {
+        // Given
+        let content = "gs='git status'\nll='ls -la'\nrun-help=man\n...=../..\n";
+
+        // When
+        let syntax = Syntax::detect(content, Syntax::Posix);
+        let parsed = parse(content, syntax);
+
+        // Then
+        assert_eq!(syntax, Syntax::AliasOutput);
+        assert_eq!(
+            aliases(&parsed),
+            vec![
+                ("gs", &alias("git status")),
+                ("ll", &alias("ls -la")),
+                ("run-help", &alias("man")),
+            ]
+        );
+        assert_eq!(parsed.skipped.len(), 1);
+        assert!(parsed.skipped[0].reason.contains("'...'"));
+    }
+
+    #[test]
+    fn given_rc_file_when_detecting_then_keeps_fallback() {
+        // Given
+        let content = "export EDITOR=vim\nalias gs='git status'\nFOO=bar\n";
+
+        // When
+        let syntax = Syntax::detect(content, Syntax::Posix);
+
+        // Then
+        assert_eq!(syntax, Syntax::Posix);
+        assert_eq!(
+            aliases(&parse(content, syntax)),
+            vec![("gs", &alias("git status"))]
+        );
+    }
+
+    // --- Get-Alias table ---
+
+    #[test]
+    fn given_get_alias_table_when_parsing_then_splits_name_column() {
+        // Given
+        let content = "
+CommandType     Name                                               Version    Source
+-----------     ----                                               -------    ------
+Alias           % -> ForEach-Object
+Alias           gs -> Get-GitStatus                                1.1.0      posh-git
+Alias           ll -> Get-ChildItem
+Alias           verylongaliasname -> Some-VeryLongCommandName-Tha…
+
+";
+
+        // When
+        let syntax = Syntax::detect(content, Syntax::Posix);
+        let parsed = parse(content, syntax);
+
+        // Then
+        assert_eq!(syntax, Syntax::AliasTable);
+        assert_eq!(
+            aliases(&parsed),
+            vec![
+                ("gs", &alias("Get-GitStatus")),
+                ("ll", &alias("Get-ChildItem"))
+            ]
+        );
+        assert_eq!(parsed.found[0].line, 5);
+        assert_eq!(parsed.skipped.len(), 2);
+        assert!(parsed.skipped[1].reason.contains("Format-Table"));
+    }
+
+    #[test]
+    fn given_name_and_definition_columns_when_parsing_then_reads_both() {
+        // Given
+        let content = "Name Definition\n---- ----------\ng    git\nnp   C:\\Windows\\notepad.exe\n";
+
+        // When
+        let parsed = parse(content, Syntax::AliasTable);
+
+        // Then
+        assert_eq!(
+            aliases(&parsed),
+            vec![
+                ("g", &alias("git")),
+                ("np", &alias(r"C:\Windows\notepad.exe"))
+            ]
+        );
+    }
+
+    // --- oh-my-zsh ---
+
+    #[test]
+    fn given_zshrc_when_reading_enabled_plugins_then_lists_them() {
+        // Given
+        let zshrc = "ZSH_THEME=robbyrussell\nplugins=(\n  git # version control\n  docker\n)\nsource $ZSH/oh-my-zsh.sh\n";
+
+        // When
+        let plugins = enabled_plugins(zshrc);
+
+        // Then
+        assert_eq!(
+            plugins,
+            Some(vec![String::from("git"), String::from("docker")])
+        );
+        assert_eq!(enabled_plugins("source ~/.aliases"), None);
+    }
+
+    #[test]
+    fn given_oh_my_zsh_dir_when_listing_plugins_then_keeps_enabled_and_prefers_custom() {
+        // Given
+        let dir = std::env::temp_dir().join(format!("akash-test-omz-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for plugin in [
+            "plugins/git",
+            "plugins/docker",
+            "plugins/npm",
+            "custom/plugins/git",
+        ] {
+            let plugin_dir = dir.join(plugin);
+            std::fs::create_dir_all(&plugin_dir).unwrap();
+            let name = plugin_dir
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string();
+            std::fs::write(plugin_dir.join(format!("{}.plugin.zsh", name)), "").unwrap();
+        }
+        let enabled = vec![
+            String::from("git"),
+            String::from("npm"),
+            String::from("missing"),
+        ];
+
+        // When
+        let enabled_files = plugin_files(&dir, Some(&enabled)).unwrap();
+        let all_files = plugin_files(&dir.join("plugins"), Some(&enabled)).unwrap();
+
+        // Then
+        assert_eq!(
+            enabled_files,
+            vec![
+                dir.join("custom/plugins/git/git.plugin.zsh"),
+                dir.join("plugins/npm/npm.plugin.zsh"),
+            ]
+        );
+        assert_eq!(all_files.len(), 3);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,7 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::Colorize;
 use std::io::{self, BufRead, Write};
+use std::process::Command;
 
 use crate::config::Config;
 use crate::shell::Shell;
@@ -45,15 +46,15 @@ pub fn run(config: &Config, shell: &dyn Shell) -> Result<()> {
         let choice = input.trim();
 
         match choice {
-            "1" => interactive_add(config)?,
+            "1" => interactive_add(config, shell)?,
             "2" => interactive_remove(config)?,
             "3" => interactive_list(config)?,
             "4" => {
                 // Reuse the cmd_apply logic from main
-                crate::cmd_apply(config, shell)?;
+                crate::cmd_apply(config, shell, false, false, false)?;
             }
             "5" => {
-                crate::cmd_init(config, shell)?;
+                crate::cmd_init(config, shell, false, false)?;
             }
             "q" | "Q" | "quit" | "exit" => {
                 println!("Goodbye!");
@@ -74,7 +75,50 @@ fn prompt(label: &str) -> Result<String> {
     Ok(input.trim().to_string())
 }
 
-fn interactive_add(config: &Config) -> Result<()> {
+/// Ask a yes/no question; anything but "y" or "yes" is a no.
+pub fn confirm(question: &str) -> Result<bool> {
+    let answer = prompt(&format!("{} [y/N]", question))?;
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Open `initial` in the user's editor ($VISUAL, $EDITOR, or a platform default)
+/// and return the saved text.
+pub fn edit_text(initial: &str, file_name: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(windows) {
+                String::from("notepad")
+            } else {
+                String::from("vi")
+            }
+        });
+
+    let path = std::env::temp_dir().join(format!("akash-{}-{}", std::process::id(), file_name));
+    std::fs::write(&path, initial)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    // $EDITOR may carry arguments, e.g. "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("$EDITOR is empty"))?;
+    let status = Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("Failed to launch editor '{}'", editor));
+
+    let text = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    if !status?.success() {
+        anyhow::bail!("Editor '{}' exited with an error, nothing saved", editor);
+    }
+    text.with_context(|| format!("Failed to read {}", path.display()))
+}
+
+fn interactive_add(config: &Config, shell: &dyn Shell) -> Result<()> {
     let name = prompt("Alias name")?;
     let command = prompt("Command")?;
 
@@ -84,6 +128,9 @@ fn interactive_add(config: &Config) -> Result<()> {
     }
 
     AliasStore::validate_alias_name(&name)?;
+    if crate::shell::parse_placeholders(&command).is_some() {
+        crate::check_function_name(config, Some(shell.shell_type()), &name)?;
+    }
 
     let mut store = AliasStore::store_load(config.aliases_path.as_ref())?;
     let is_new = store.add_alias(name.clone(), command.clone());
@@ -118,8 +165,9 @@ fn interactive_remove(config: &Config) -> Result<()> {
 fn interactive_list(config: &Config) -> Result<()> {
     let store = AliasStore::store_load(config.aliases_path.as_ref())?;
     let aliases = store.list_aliases();
+    let functions = store.list_functions();
 
-    if aliases.is_empty() {
+    if aliases.is_empty() && functions.is_empty() {
         println!("No aliases defined yet.");
         return Ok(());
     }
@@ -127,13 +175,23 @@ fn interactive_list(config: &Config) -> Result<()> {
     let max_len = aliases.keys().map(|k| k.len()).max().unwrap_or(0);
 
     println!("{}", "Aliases:".bold());
-    for (name, command) in aliases {
+    for (name, entry) in aliases {
         println!(
             "  {:width$}  ->  {}",
             name.green(),
-            command,
+            entry.command,
             width = max_len
         );
     }
+    if !functions.is_empty() {
+        println!("{}", "Functions:".bold());
+        for (name, function) in functions {
+            println!(
+                "  {}  ({} lines)",
+                name.green(),
+                function.body.lines().count()
+            );
+        }
+    }
     Ok(())
 }
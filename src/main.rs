@@ -1,25 +1,48 @@
+mod backup;
+mod block;
 mod cli;
 mod config;
+mod conflict;
+mod diff;
+mod export;
+mod fsutil;
+mod graph;
+mod import;
 mod interactive;
+mod lint;
 mod shell;
+mod state;
+mod status;
 mod store;
+mod syntax;
+mod timestamp;
 
 use anyhow::{Context, Result};
+use backup::Backups;
 use clap::Parser;
 use colored::Colorize;
-use config::Config;
-use shell::Shell;
-use store::AliasStore;
+use config::{ApplyMode, Config, ConflictPolicy};
+use conflict::{Conflict, ConflictChecker};
+use graph::AliasGraph;
+use lint::{Finding, Linter, Severity};
+use shell::{Shell, ShellType};
+use state::AppliedState;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use store::{AliasEntry, AliasFilter, AliasStore};
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     // Create config file if missing (before loading or running any commands)
     Config::create_default_if_missing()?;
     // Load config first (before tracing, since it controls log level)
     let config = Config::load()?;
 
     // Initialize tracing (adjust based on your setup)
+    // Logs go to stderr: stdout of `akash hook` is evaluated by the shell
     tracing_subscriber::fmt()
         .with_max_level(config.tracing_level())
+        .with_writer(io::stderr)
         .init();
 
     let cli = cli::Cli::parse();
@@ -27,26 +50,157 @@ fn main() -> Result<()> {
         .shell
         .or_else(|| config.shell.as_deref().and_then(|s| s.parse().ok()));
 
-    let shell = shell::get_shell(shell_override)?;
+    // Resolved on demand: detection walks the process table, which `hook` and `list` can skip
+    let resolve_shell = || shell::get_shell(shell_override);
+
+    // Set by dry runs and status checks that found differences, so scripts can test the exit code
+    let mut changes_pending = false;
 
     match cli.command {
-        Some(cli::Command::Add { name, command }) => cmd_add(&config, &name, &command)?,
-        Some(cli::Command::Remove { name }) => cmd_remove(&config, &name, shell.as_ref())?,
-        Some(cli::Command::List) => cmd_list(&config)?,
-        Some(cli::Command::Apply) => cmd_apply(&config, shell.as_ref())?,
-        Some(cli::Command::Init) => cmd_init(&config, shell.as_ref())?,
-        None => interactive::run(&config, shell.as_ref())?,
+        Some(cli::Command::Add {
+            name,
+            command,
+            function,
+            for_shell,
+            description,
+            tag,
+            force,
+            strict,
+        }) => match command {
+            Some(command) if !function => cmd_add(
+                &config,
+                resolve_shell()?.as_ref(),
+                &name,
+                &command,
+                description,
+                tag,
+                force,
+                strict,
+            )?,
+            _ => cmd_add_function(
+                &config,
+                resolve_shell()?.as_ref(),
+                &name,
+                for_shell,
+                force,
+                strict,
+            )?,
+        },
+        Some(cli::Command::Remove { name, dry_run }) => {
+            changes_pending =
+                cmd_remove(&config, &name, resolve_shell()?.as_ref(), dry_run)? && dry_run
+        }
+        Some(cli::Command::List {
+            tag,
+            author,
+            search,
+            long,
+        }) => {
+            let filter = AliasFilter {
+                tags: tag,
+                author,
+                search,
+            };
+            cmd_list(&config, &filter, long)?
+        }
+        Some(cli::Command::Apply {
+            dry_run,
+            all,
+            force,
+            strict,
+        }) => {
+            let shell = resolve_shell()?;
+            let changed = if all {
+                cmd_apply_all(&config, shell.as_ref(), dry_run, force, strict)?
+            } else {
+                cmd_apply(&config, shell.as_ref(), dry_run, force, strict)?
+            };
+            changes_pending = changed && dry_run
+        }
+        Some(cli::Command::Init {
+            target,
+            dry_run,
+            print,
+            force,
+        }) => {
+            let shell = match target {
+                Some(shell_type) => shell::get_shell(Some(shell_type))?,
+                None => resolve_shell()?,
+            };
+            if print {
+                cmd_hook(&config, shell.as_ref())?
+            } else {
+                changes_pending = cmd_init(&config, shell.as_ref(), dry_run, force)? && dry_run
+            }
+        }
+        Some(cli::Command::Hook { target }) => {
+            let shell = match target {
+                Some(shell_type) => shell::get_shell(Some(shell_type))?,
+                None => resolve_shell()?,
+            };
+            cmd_hook(&config, shell.as_ref())?
+        }
+        Some(cli::Command::Status) => {
+            changes_pending = cmd_status(&config, resolve_shell()?.as_ref())?
+        }
+        Some(cli::Command::Check) => {
+            changes_pending = cmd_check(&config, resolve_shell()?.as_ref())?
+        }
+        Some(cli::Command::Expand { name }) => cmd_expand(&config, &name)?,
+        Some(cli::Command::Uninstall { dry_run, purge }) => {
+            changes_pending = cmd_uninstall(&config, dry_run, purge)? && dry_run
+        }
+        Some(cli::Command::Import {
+            from,
+            overwrite,
+            yes,
+            dry_run,
+            strict,
+        }) => {
+            changes_pending =
+                cmd_import(&config, &from, overwrite, yes, dry_run, strict)? && dry_run
+        }
+        Some(cli::Command::Export { format, output }) => {
+            cmd_export(&config, format, output.as_deref())?
+        }
+        Some(cli::Command::Restore { id, list }) => {
+            cmd_restore(&config, resolve_shell()?.as_ref(), id.as_deref(), list)?
+        }
+        None => interactive::run(&config, resolve_shell()?.as_ref())?,
     }
 
-    Ok(())
+    if changes_pending {
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
 }
 
 // ============================================================================
 // COMMANDS
 // ============================================================================
 
-fn cmd_add(config: &Config, name: &str, command: &str) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+fn cmd_add(
+    config: &Config,
+    shell: &dyn Shell,
+    name: &str,
+    command: &str,
+    description: Option<String>,
+    tags: Vec<String>,
+    force: bool,
+    strict: bool,
+) -> Result<()> {
     AliasStore::validate_alias_name(name)?;
+    if shell::parse_placeholders(command).is_some() {
+        check_function_name(config, Some(shell.shell_type()), name)?;
+    }
+    enforce_conflict_policy(config, shell, force, |checker| {
+        checker.check(name, command).into_iter().collect()
+    })?;
+    enforce_lint(
+        strict,
+        &Linter::new(&config.lint_allow).check(name, command),
+    )?;
 
     let mut store = AliasStore::store_load(config.aliases_path.as_ref())?;
 
@@ -59,8 +213,40 @@ fn cmd_add(config: &Config, name: &str, command: &str) -> Result<()> {
     }
 
     let is_new = store.add_alias(name.to_string(), command.to_string());
+    if let Some(entry) = store.alias_mut(name) {
+        // Metadata is only replaced when given, so `add` can update just the command
+        if description.is_some() {
+            entry.description = description;
+        }
+        if !tags.is_empty() {
+            entry.tags = tags;
+        }
+    }
+
+    // A loop never reaches a command: the shell ends up running an alias name
+    let graph = AliasGraph::new(&store);
+    let cycle = graph.cycle_of(name);
+    if let Some(cycle) = &cycle {
+        let description = graph::describe_cycle(cycle);
+        if !force {
+            anyhow::bail!(
+                "'{}' would make a cycle of aliases: {} (use --force to add it anyway)",
+                name,
+                description
+            );
+        }
+        println!("{} cycle of aliases: {}", "Warning:".yellow(), description);
+    }
+    let chain = graph.chain(name);
     store.store_save(config.aliases_path.as_ref())?;
 
+    if cycle.is_none() && chain.len() > 2 {
+        println!(
+            "{} {}",
+            "Runs through:".dimmed(),
+            chain.join(" -> ").dimmed()
+        );
+    }
     if is_new {
         println!("{} {} -> {}", "Added:".green(), name.bold(), command);
     } else {
@@ -70,24 +256,133 @@ fn cmd_add(config: &Config, name: &str, command: &str) -> Result<()> {
     Ok(())
 }
 
-fn cmd_remove(config: &Config, name: &str, shell: &dyn Shell) -> Result<()> {
+fn cmd_add_function(
+    config: &Config,
+    shell: &dyn Shell,
+    name: &str,
+    for_shell: Option<ShellType>,
+    force: bool,
+    strict: bool,
+) -> Result<()> {
+    AliasStore::validate_alias_name(name)?;
+    // A variant for another shell never reaches sh
+    match for_shell {
+        Some(ShellType::Sh) | None => {
+            check_function_name(config, Some(for_shell.unwrap_or(shell.shell_type())), name)?
+        }
+        Some(_) => {}
+    }
+
     let mut store = AliasStore::store_load(config.aliases_path.as_ref())?;
+    let shell_key = for_shell.map(|shell_type| shell_type.key());
 
-    if store.remove_alias(name) {
-        store.store_save(config.aliases_path.as_ref())?;
-        println!("{} {}", "Removed:".green(), name.bold());
-        cmd_apply(config, shell)?;
+    // Body comes from a pipe (akash add --function f < f.sh) or from $EDITOR
+    let body = if io::stdin().is_terminal() {
+        let current = store
+            .functions
+            .get(name)
+            .map(|function| match shell_key {
+                Some(key) => function.variants.get(key).cloned().unwrap_or_default(),
+                None => function.body.clone(),
+            })
+            .unwrap_or_default();
+        let extension = match for_shell {
+            Some(ShellType::PowerShell) => "ps1",
+            Some(ShellType::Fish) => "fish",
+            Some(ShellType::Nushell) => "nu",
+            Some(ShellType::Cmd) => "cmd",
+            _ => "sh",
+        };
+        interactive::edit_text(&current, &format!("{}.{}", name, extension))?
     } else {
-        println!("{} alias '{}' not found", "Error:".red(), name);
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .context("Failed to read function body from stdin")?;
+        input
+    };
+
+    let body = body.trim_end().to_string();
+    if body.trim().is_empty() {
+        anyhow::bail!("Function body is empty, nothing saved");
     }
+    // A variant is checked against its own shell
+    let target_shell = match for_shell {
+        Some(shell_type) => shell::get_shell(Some(shell_type))?,
+        None => shell::get_shell(Some(shell.shell_type()))?,
+    };
+    enforce_conflict_policy(config, target_shell.as_ref(), force, |checker| {
+        checker.check(name, &body).into_iter().collect()
+    })?;
+    enforce_lint(strict, &Linter::new(&config.lint_allow).check(name, &body))?;
+
+    if store.aliases.contains_key(name) {
+        println!(
+            "{} alias '{}' will be replaced by a function",
+            "Warning:".yellow(),
+            name
+        );
+    }
+
+    let line_count = body.lines().count();
+    let is_new = store.add_function(name.to_string(), body, shell_key);
+    store.store_save(config.aliases_path.as_ref())?;
+
+    let target = match for_shell {
+        Some(shell_type) => format!(" ({} only)", shell_type.key()),
+        None => String::new(),
+    };
+    if is_new {
+        println!(
+            "{} function {}{} ({} lines)",
+            "Added:".green(),
+            name.bold(),
+            target,
+            line_count
+        );
+    } else {
+        println!(
+            "{} function {}{} ({} lines)",
+            "Updated:".yellow(),
+            name.bold(),
+            target,
+            line_count
+        );
+    }
+    println!("Run {} to write to your shell config", "akash apply".cyan());
     Ok(())
 }
 
-fn cmd_list(config: &Config) -> Result<()> {
+/// Returns whether the shell config changed (or would change, with `dry_run`).
+fn cmd_remove(config: &Config, name: &str, shell: &dyn Shell, dry_run: bool) -> Result<bool> {
+    let mut store = AliasStore::store_load(config.aliases_path.as_ref())?;
+
+    if !store.remove_alias(name) {
+        println!("{} alias '{}' not found", "Error:".red(), name);
+        return Ok(false);
+    }
+
+    if dry_run {
+        // The store is left untouched: only show what the config would become
+        println!("{} {}", "Would remove:".yellow(), name.bold());
+    } else {
+        store.store_save(config.aliases_path.as_ref())?;
+        println!("{} {}", "Removed:".green(), name.bold());
+    }
+    apply_store(config, shell, &store, dry_run)
+}
+
+fn cmd_list(config: &Config, filter: &AliasFilter, long: bool) -> Result<()> {
     let store = AliasStore::store_load(config.aliases_path.as_ref())?;
     let aliases = store.list_aliases();
+    // Functions carry no metadata, so they only show up in an unfiltered listing
+    let functions = if filter.is_empty() {
+        store.list_functions().iter().collect()
+    } else {
+        Vec::new()
+    };
 
-    if aliases.is_empty() {
+    if aliases.is_empty() && functions.is_empty() {
         println!(
             "No aliases defined. Use {} to create one.",
             "akash add <name> <command>".cyan()
@@ -95,124 +390,1221 @@ fn cmd_list(config: &Config) -> Result<()> {
         return Ok(());
     }
 
+    let matching: Vec<(&String, &AliasEntry)> = aliases
+        .iter()
+        .filter(|(name, entry)| filter.matches(name, entry))
+        .collect();
+
+    if matching.is_empty() && functions.is_empty() {
+        println!("No aliases match the given filters.");
+        return Ok(());
+    }
+
     // Find the longest alias name for alignment
-    let max_len = aliases.keys().map(|k| k.len()).max().unwrap_or(0);
+    let max_len = matching
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
 
-    println!("{}", "Aliases:".bold());
-    for (name, command) in aliases {
-        println!(
+    if !matching.is_empty() {
+        println!("{}", "Aliases:".bold());
+    }
+    for (name, entry) in matching {
+        let mut line = format!(
             "  {:width$}  ->  {}",
             name.green(),
-            command,
+            entry.command,
             width = max_len
         );
+        if !entry.tags.is_empty() {
+            line.push_str(&format!("  [{}]", entry.tags.join(", ")).cyan().to_string());
+        }
+        if let Some(description) = &entry.description {
+            line.push_str(&format!("  # {}", description).dimmed().to_string());
+        }
+        println!("{}", line);
+
+        if long {
+            let unknown = || String::from("-");
+            println!(
+                "  {:width$}      {}",
+                "",
+                format!(
+                    "author: {}, created: {}, updated: {}",
+                    entry.author.clone().unwrap_or_else(unknown),
+                    entry.created_at.clone().unwrap_or_else(unknown),
+                    entry.updated_at.clone().unwrap_or_else(unknown)
+                )
+                .dimmed(),
+                width = max_len
+            );
+        }
+    }
+
+    if !functions.is_empty() {
+        println!("{}", "Functions:".bold());
+        for (name, function) in functions {
+            let mut shells: Vec<&str> = function.variants.keys().map(String::as_str).collect();
+            if !function.body.is_empty() {
+                shells.insert(0, "default");
+            }
+            println!(
+                "  {}  ({} lines, {})",
+                name.green(),
+                function.body.lines().count(),
+                shells.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Check a name that becomes a shell function. sh (dash) rejects names that are not plain
+/// identifiers, so refuse them when `shell` or the `shells` list is sh, and warn otherwise.
+pub fn check_function_name(config: &Config, shell: Option<ShellType>, name: &str) -> Result<()> {
+    if AliasStore::is_portable_function_name(name) {
+        return Ok(());
+    }
+    let uses_sh = shell == Some(ShellType::Sh) || config.shell_types()?.contains(&ShellType::Sh);
+    if uses_sh {
+        anyhow::bail!(
+            "'{}' cannot name a function in sh: use letters, digits and underscores (e.g. '{}')",
+            name,
+            name.replace('-', "_")
+        );
     }
+    println!(
+        "{} '{}' becomes a function, which sh (dash) cannot define: use letters, digits and underscores to keep it portable",
+        "Warning:".yellow(),
+        name
+    );
     Ok(())
 }
 
-pub fn cmd_apply(config: &Config, shell: &dyn Shell) -> Result<()> {
+/// Returns whether the shell config changed (or would change, with `dry_run`).
+pub fn cmd_apply(
+    config: &Config,
+    shell: &dyn Shell,
+    dry_run: bool,
+    force: bool,
+    strict: bool,
+) -> Result<bool> {
     let store = AliasStore::store_load(config.aliases_path.as_ref())?;
+    enforce_lint(strict, &Linter::new(&config.lint_allow).check_store(&store))?;
+    enforce_conflict_policy(config, shell, force, |checker| checker.check_store(&store))?;
+    warn_alias_cycles(&store);
+    let changed = apply_store(config, shell, &store, dry_run)?;
+
+    if let Some(line) = BlockTarget::resolve(config, shell)?.loader_line()
+        && !is_installed(shell, line)?
+    {
+        println!(
+            "{} {} does not load your aliases yet, run: {}",
+            "Note:".yellow(),
+            shell.config_path()?.display(),
+            "akash init".cyan()
+        );
+    }
+    Ok(changed)
+}
+
+/// Apply to every shell in the `shells` config list, or if it is empty, to the
+/// current shell and every shell whose config already has an akash block.
+/// Returns whether any config changed; fails if any shell failed.
+fn cmd_apply_all(
+    config: &Config,
+    current: &dyn Shell,
+    dry_run: bool,
+    force: bool,
+    strict: bool,
+) -> Result<bool> {
+    let mut shell_types = config.shell_types()?;
+    if shell_types.is_empty() {
+        shell_types.push(current.shell_type());
+        for shell_type in ShellType::ALL {
+            let shell = shell::get_shell(Some(shell_type))?;
+            let content = std::fs::read_to_string(shell.config_path()?).unwrap_or_default();
+            let has_block =
+                block::locate_block(&content, &shell.begin_marker(), &shell.end_marker())
+                    .map_or(true, |span| span.is_some());
+            if has_block && !shell_types.contains(&shell_type) {
+                shell_types.push(shell_type);
+            }
+        }
+    }
+
+    let store = AliasStore::store_load(config.aliases_path.as_ref())?;
+    // The lint does not depend on the shell: it stops all of them at once
+    enforce_lint(strict, &Linter::new(&config.lint_allow).check_store(&store))?;
+    warn_alias_cycles(&store);
+    let mut results = Vec::new();
+    for shell_type in shell_types {
+        let shell = shell::get_shell(Some(shell_type))?;
+        println!("{}", format!("==> {}", shell.name()).bold());
+        // One broken config must not stop the other shells
+        let result = enforce_conflict_policy(config, shell.as_ref(), force, |checker| {
+            checker.check_store(&store)
+        })
+        .and_then(|()| apply_store(config, shell.as_ref(), &store, dry_run));
+        if let Err(error) = &result {
+            println!("{} {:#}", "Error:".red(), error);
+        }
+        println!();
+        results.push((shell, result));
+    }
+
+    println!("{}", "Summary:".bold());
+    let mut failed = 0;
+    let mut any_changed = false;
+    for (shell, result) in &results {
+        let target = BlockTarget::resolve(config, shell.as_ref())?;
+        let location = match target.file() {
+            Some(path) => path.display().to_string(),
+            None => String::from("akash hook"),
+        };
+        let outcome = match result {
+            Ok(true) if dry_run => "would change".yellow(),
+            Ok(true) => "updated".green(),
+            Ok(false) => "unchanged".normal(),
+            Err(_) => "failed".red(),
+        };
+        println!("  {:<10} {:<13} {}", shell.name(), outcome, location);
+        match result {
+            Ok(changed) => any_changed |= changed,
+            Err(_) => failed += 1,
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} shells failed", failed, results.len());
+    }
+    Ok(any_changed)
+}
+
+pub fn cmd_init(config: &Config, shell: &dyn Shell, dry_run: bool, force: bool) -> Result<bool> {
+    let store = AliasStore::store_load(config.aliases_path.as_ref())?;
+    enforce_lint(false, &Linter::new(&config.lint_allow).check_store(&store))?;
+    enforce_conflict_policy(config, shell, force, |checker| checker.check_store(&store))?;
+    warn_alias_cycles(&store);
+    let mut changed = apply_store(config, shell, &store, dry_run)?;
+
+    // Source and hook modes: the shell config gets a single line loading the aliases
+    let target = BlockTarget::resolve(config, shell)?;
+    if let Some(line) = target.loader_line() {
+        let config_path = shell.config_path()?;
+        let block = [shell.begin_marker(), line.to_string(), shell.end_marker()].join("\n");
+        changed |= write_block(config, shell, &config_path, &block, dry_run, true)?;
+        if !dry_run {
+            println!(
+                "{} {} now runs: {}",
+                "Installed:".green(),
+                config_path.display(),
+                line
+            );
+        }
+    }
+
+    if dry_run {
+        return Ok(changed);
+    }
+    println!(
+        "\n{} akash initialized for {}.",
+        "Ready!".green().bold(),
+        shell.name().bold()
+    );
+    println!(
+        "Your aliases will be loaded when you open a new {} session.",
+        shell.name()
+    );
+    Ok(changed)
+}
+
+/// Print the alias block to stdout, for the shell to evaluate at startup.
+/// Nothing else may be printed here: it would be executed too.
+/// Aliases that fail the safety lint or would break the shell are left out, with a
+/// comment in the output and a warning on stderr, so one bad entry cannot break startup.
+fn cmd_hook(config: &Config, shell: &dyn Shell) -> Result<()> {
+    let mut store = AliasStore::store_load(config.aliases_path.as_ref())?;
+    let mut skipped: Vec<(String, String)> = Linter::new(&config.lint_allow)
+        .check_store(&store)
+        .into_iter()
+        .filter(|finding| finding.is_fatal(false))
+        .map(|finding| {
+            let reason = format!("{} [{}]", finding.message, finding.rule);
+            (finding.name, reason)
+        })
+        .collect();
+    for (name, _) in &skipped {
+        store.remove_alias(name);
+    }
+
+    // Only the built-in tokenizer: this runs at every shell startup, where spawning
+    // `bash -n` or a whole PowerShell would defeat the point of the hook.
+    // `akash apply` runs the shell's own check once instead.
+    let broken =
+        syntax::tokenizer_broken_entries(shell.shell_type(), &shell.generate_entries(&store));
+    for (name, _) in &broken {
+        store.remove_alias(name);
+    }
+    skipped.extend(broken);
+
+    for (name, reason) in &skipped {
+        eprintln!(
+            "{} akash hook skipped '{}': {} (fix it with akash add)",
+            "Warning:".yellow(),
+            name,
+            reason
+        );
+        println!(
+            "{} akash skipped '{}': {}",
+            shell.comment_prefix(),
+            name,
+            reason.replace(['\n', '\r'], " ")
+        );
+    }
+    println!("{}", shell.generate_alias_block(&store));
+    Ok(())
+}
+
+/// Where the alias block of one shell goes, depending on `apply_mode`.
+enum BlockTarget {
+    /// Written into the shell config itself
+    Inline(PathBuf),
+    /// Written to a generated file, which the shell config loads with `line`
+    Sourced { path: PathBuf, line: String },
+    /// Printed by `akash hook` at startup, which the shell config runs with `line`
+    Hook { line: String },
+}
+
+impl BlockTarget {
+    /// Shells that cannot source a file or evaluate a hook fall back to inline.
+    fn resolve(config: &Config, shell: &dyn Shell) -> Result<Self> {
+        match config.apply_mode {
+            ApplyMode::Inline => {}
+            ApplyMode::Source => {
+                let path = shell.generated_path()?;
+                if let Some(line) = shell.source_line(&path) {
+                    return Ok(Self::Sourced { path, line });
+                }
+            }
+            ApplyMode::Hook => {
+                if let Some(line) = shell.hook_line() {
+                    return Ok(Self::Hook { line });
+                }
+            }
+        }
+        Ok(Self::Inline(shell.config_path()?))
+    }
+
+    /// File that receives the alias block (none in hook mode)
+    fn file(&self) -> Option<&Path> {
+        match self {
+            Self::Inline(path) | Self::Sourced { path, .. } => Some(path),
+            Self::Hook { .. } => None,
+        }
+    }
+
+    /// Line the shell config needs in its managed block (none in inline mode)
+    fn loader_line(&self) -> Option<&str> {
+        match self {
+            Self::Inline(_) => None,
+            Self::Sourced { line, .. } | Self::Hook { line } => Some(line),
+        }
+    }
+}
+
+/// Write the store's block to its target file, or with `dry_run` print the diff instead.
+fn apply_store(
+    config: &Config,
+    shell: &dyn Shell,
+    store: &AliasStore,
+    dry_run: bool,
+) -> Result<bool> {
     let aliases = store.list_aliases();
+    let target = BlockTarget::resolve(config, shell)?;
+
+    // A block the shell cannot parse would break every new terminal.
+    // In hook mode this is the shell's only check: `akash hook` only runs the tokenizer
+    syntax::validate(shell, store)?;
+
+    let Some(path) = target.file() else {
+        // Hook mode: the shell reads the store itself at startup
+        println!(
+            "{} {} runs `akash hook` at startup, there is nothing to write",
+            "Hook mode:".cyan(),
+            shell.name()
+        );
+        return Ok(false);
+    };
 
-    let block = shell.generate_alias_block(aliases);
+    // The generated file belongs to akash: no backups needed
+    let block = shell.generate_alias_block(store);
+    let backup = matches!(target, BlockTarget::Inline(_));
+    let changed = write_block(config, shell, path, &block, dry_run, backup)?;
+    if dry_run {
+        return Ok(changed);
+    }
+    AppliedState::open_default()?
+        .record(path, shell.generate_entries(store).into_iter().collect())?;
+
+    if aliases.is_empty() && store.functions.is_empty() {
+        println!(
+            "{} Cleared all aliases from {}",
+            "Done!".green().bold(),
+            path.display()
+        );
+    } else {
+        println!(
+            "{} Wrote {} aliases and {} functions to {}",
+            "Done!".green().bold(),
+            aliases.len(),
+            store.functions.len(),
+            path.display()
+        );
+    }
+
+    println!("{}", shell.reload_instructions().cyan());
+    Ok(changed)
+}
+
+/// Whether the shell config's managed block holds exactly `line`.
+fn is_installed(shell: &dyn Shell, line: &str) -> Result<bool> {
     let config_path = shell.config_path()?;
+    let content = std::fs::read_to_string(&config_path).unwrap_or_default();
+    Ok(
+        match block::locate_block(&content, &shell.begin_marker(), &shell.end_marker()) {
+            Ok(Some(span)) => span.lines(&content) == [line],
+            _ => false,
+        },
+    )
+}
 
-    // Read existing config (or empty string if file doesn't exist)
-    let content = if config_path.exists() {
-        std::fs::read_to_string(&config_path)
-            .with_context(|| format!("Failed to read {}", config_path.display()))?
+/// Put `block` between the markers of the file at `path` (appending it if absent).
+/// With `dry_run` the diff is printed and nothing is written. Returns whether the file changes.
+fn write_block(
+    config: &Config,
+    shell: &dyn Shell,
+    path: &Path,
+    block: &str,
+    dry_run: bool,
+    backup: bool,
+) -> Result<bool> {
+    // Read existing file (or empty string if it doesn't exist)
+    let content = if path.exists() {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
     } else {
         String::new()
     };
 
     let new_content =
-        replace_or_append_block(&content, &shell.begin_marker(), &shell.end_marker(), &block);
+        block::replace_or_append_block(&content, &shell.begin_marker(), &shell.end_marker(), block)
+            .with_context(|| {
+                format!(
+                    "Refusing to update {} (fix the akash markers by hand or run: akash restore)",
+                    path.display()
+                )
+            })?;
+    let changed = new_content != content;
+
+    if dry_run {
+        let label = path.display().to_string();
+        match diff::unified_diff(&content, &new_content, &label, true) {
+            Some(diff) => {
+                println!("{}", diff);
+                println!(
+                    "\n{} nothing was written (run without --dry-run to apply)",
+                    "Dry run:".yellow().bold()
+                );
+            }
+            None => println!(
+                "{} {} is up to date",
+                "No changes:".green().bold(),
+                path.display()
+            ),
+        }
+        return Ok(changed);
+    }
 
     // Create parent directories if needed
-    if let Some(parent) = config_path.parent() {
+    if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)
             .with_context(|| format!("Failed to create directory {}", parent.display()))?;
     }
 
-    std::fs::write(&config_path, new_content)
-        .with_context(|| format!("Failed to write {}", config_path.display()))?;
+    if changed {
+        if backup
+            && let Some(record) = Backups::open_default()?.create(path, config.backup_retention)?
+        {
+            println!(
+                "{}",
+                format!(
+                    "Backed up {} as {} (undo with: akash restore)",
+                    path.display(),
+                    record.id
+                )
+                .dimmed()
+            );
+        }
 
+        fsutil::write_atomic(path, new_content.as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(changed)
+}
+
+/// Report aliases that shadow a command, builtin or keyword of `shell`, as `conflict_policy`
+/// says: warn, or fail unless `force`. `find` is only run when the policy checks anything.
+fn enforce_conflict_policy(
+    config: &Config,
+    shell: &dyn Shell,
+    force: bool,
+    find: impl FnOnce(&ConflictChecker) -> Vec<Conflict>,
+) -> Result<()> {
+    if config.conflict_policy == ConflictPolicy::Ignore {
+        return Ok(());
+    }
+    let conflicts = find(&ConflictChecker::new(shell));
+    for conflict in &conflicts {
+        println!(
+            "{} '{}' {}",
+            "Warning:".yellow(),
+            conflict.name,
+            conflict.describe(shell)
+        );
+    }
+
+    if !conflicts.is_empty() && config.conflict_policy == ConflictPolicy::Refuse && !force {
+        anyhow::bail!(
+            "{} alias(es) shadow existing commands in {} (use --force to keep them, or set conflict_policy = \"warn\")",
+            conflicts.len(),
+            shell.name()
+        );
+    }
+    Ok(())
+}
+
+/// Print the safety lint findings. Dangerous commands stop the command, and so do
+/// warnings with `strict`, unless `lint_allow` turns the rule off for the alias.
+fn enforce_lint(strict: bool, findings: &[Finding]) -> Result<()> {
+    for finding in findings {
+        print_finding(finding);
+    }
+
+    let mut fatal: Vec<&str> = findings
+        .iter()
+        .filter(|finding| finding.is_fatal(strict))
+        .map(|finding| finding.name.as_str())
+        .collect();
+    fatal.dedup();
+    if !fatal.is_empty() {
+        anyhow::bail!(
+            "{} alias(es) failed the safety lint: {} (to keep one, allow its rule under [lint_allow] in {})",
+            fatal.len(),
+            fatal.join(", "),
+            Config::path()?.display()
+        );
+    }
+    Ok(())
+}
+
+fn print_finding(finding: &Finding) {
+    let label = match finding.severity {
+        Severity::Danger => "Danger:".red().bold(),
+        Severity::Warning => "Warning:".yellow(),
+    };
     println!(
-        "{} Wrote {} aliases to {}",
-        "Done!".green().bold(),
-        aliases.len(),
-        config_path.display()
+        "{} '{}' {} [{}]",
+        label, finding.name, finding.message, finding.rule
     );
+}
 
-    if aliases.is_empty() {
+/// Print a warning for each cycle of aliases in the store.
+fn warn_alias_cycles(store: &AliasStore) {
+    for cycle in AliasGraph::new(store).cycles() {
         println!(
-            "{} Cleared all aliases from {}",
-            "Done!".green().bold(),
-            config_path.display()
+            "{} cycle of aliases: {} (these aliases never reach a command)",
+            "Warning:".yellow(),
+            graph::describe_cycle(&cycle)
+        );
+    }
+}
+
+/// List the aliases that shadow a command, builtin or keyword of `shell` (whatever the
+/// policy), cycles of aliases, aliases running through other aliases, and safety lint findings.
+/// Returns whether there are conflicts, cycles or findings.
+fn cmd_check(config: &Config, shell: &dyn Shell) -> Result<bool> {
+    let store = AliasStore::store_load(config.aliases_path.as_ref())?;
+    let conflicts = ConflictChecker::new(shell).check_store(&store);
+    let graph = AliasGraph::new(&store);
+    let cycles = graph.cycles();
+    let chains = graph.chains();
+    let findings = Linter::new(&config.lint_allow).check_store(&store);
+
+    if conflicts.is_empty() {
+        println!(
+            "{} no alias shadows a command of {}",
+            "OK:".green().bold(),
+            shell.name()
         );
     } else {
+        let width = conflicts.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        println!("{}", format!("Conflicts in {}:", shell.name()).bold());
+        for conflict in &conflicts {
+            println!(
+                "  {:width$}  {}",
+                conflict.name.yellow(),
+                conflict.describe(shell),
+                width = width
+            );
+        }
         println!(
-            "{} Wrote {} aliases to {}",
-            "Done!".green().bold(),
-            aliases.len(),
-            config_path.display()
+            "Rename them, or keep them on purpose: aliases that call the command they shadow \
+             (alias ls='ls --color') are not reported"
         );
     }
 
-    println!("{}", shell.reload_instructions().cyan());
+    if cycles.is_empty() {
+        println!("{} no cycle of aliases", "OK:".green().bold());
+    } else {
+        println!(
+            "\n{}",
+            "Cycles (these aliases never reach a command):".bold()
+        );
+        for cycle in &cycles {
+            println!("  {}", graph::describe_cycle(cycle).red());
+        }
+    }
+
+    if !chains.is_empty() {
+        println!("\n{}", "Aliases running through other aliases:".bold());
+        for chain in &chains {
+            println!("  {}", chain.join(" -> "));
+        }
+    }
+
+    if findings.is_empty() {
+        println!("{} no dangerous command found", "OK:".green().bold());
+    } else {
+        println!("\n{}", "Safety lint:".bold());
+        for finding in &findings {
+            print!("  ");
+            print_finding(finding);
+        }
+    }
+    Ok(!conflicts.is_empty() || !cycles.is_empty() || !findings.is_empty())
+}
+
+/// Print each step of the expansion of an alias.
+fn cmd_expand(config: &Config, name: &str) -> Result<()> {
+    let store = AliasStore::store_load(config.aliases_path.as_ref())?;
+    if store.list_functions().contains_key(name) {
+        println!("'{}' is a function: it runs its body as is", name);
+        return Ok(());
+    }
+    let Some((steps, end)) = AliasGraph::new(&store).expand(name) else {
+        anyhow::bail!("Alias '{}' not found", name);
+    };
+
+    println!("{}", name.bold());
+    for step in &steps {
+        println!("  {} {}", "->".dimmed(), step.command);
+    }
+
+    let last = steps
+        .last()
+        .map(|step| step.command.as_str())
+        .unwrap_or_default();
+    let word = graph::first_word(last).unwrap_or_default();
+    match end {
+        graph::ExpansionEnd::Command => {}
+        graph::ExpansionEnd::SelfReference => println!(
+            "{}",
+            format!(
+                "'{}' is not expanded again: the shell runs the {} command",
+                word, word
+            )
+            .dimmed()
+        ),
+        graph::ExpansionEnd::Function => {
+            println!("{}", format!("'{}' is an akash function", word).dimmed())
+        }
+        graph::ExpansionEnd::Cycle(cycle) => println!(
+            "{} cycle of aliases: {} (the shell stops here and runs '{}' as a command)",
+            "Warning:".yellow(),
+            graph::describe_cycle(&cycle),
+            word
+        ),
+    }
     Ok(())
 }
 
-pub fn cmd_init(config: &Config, shell: &dyn Shell) -> Result<()> {
-    cmd_apply(config, shell)?;
-    println!(
-        "\n{} akash initialized for {}.",
-        "Ready!".green().bold(),
-        shell.name().bold()
-    );
+/// Report drift between the store and the managed block of each shell config
+/// (or generated file, in source mode): the current shell's, plus every other
+/// one that contains an akash block.
+/// Returns whether anything is out of sync.
+fn cmd_status(config: &Config, current: &dyn Shell) -> Result<bool> {
+    let store = AliasStore::store_load(config.aliases_path.as_ref())?;
+    let applied = AppliedState::open_default()?;
+    let mut drift_found = false;
+
+    for shell_type in ShellType::ALL {
+        let shell = shell::get_shell(Some(shell_type))?;
+        let target = BlockTarget::resolve(config, shell.as_ref())?;
+        let is_current = shell_type == current.shell_type();
+        let installed = match target.loader_line() {
+            Some(line) => Some(is_installed(shell.as_ref(), line)?),
+            None => None,
+        };
+
+        let Some(config_path) = target.file() else {
+            // Hook mode: aliases are generated at startup, only the hook line can be missing
+            if is_current || installed == Some(true) {
+                println!("{} {}", shell.name().bold(), shell.config_path()?.display());
+                if installed == Some(true) {
+                    println!("  {} (akash hook runs at startup)", "in sync".green());
+                } else {
+                    println!(
+                        "  {} hook line not installed (run: akash init)",
+                        "missing".red()
+                    );
+                    drift_found = true;
+                }
+            }
+            continue;
+        };
+        let content = match std::fs::read_to_string(config_path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("Failed to read {}", config_path.display()));
+            }
+        };
+
+        let span = block::locate_block(&content, &shell.begin_marker(), &shell.end_marker());
+        if !is_current && matches!(span, Ok(None)) {
+            continue;
+        }
+
+        println!("{} {}", shell.name().bold(), config_path.display());
+        if installed == Some(false) {
+            println!(
+                "  {} {} does not load this file (run: akash init)",
+                "missing".red(),
+                shell.config_path()?.display()
+            );
+            drift_found = true;
+        }
+        let span = match span {
+            Ok(Some(span)) => span,
+            Ok(None) => {
+                println!("  {} no akash block (run: akash init)", "missing".red());
+                drift_found = true;
+                continue;
+            }
+            Err(error) => {
+                println!("  {} {}", "broken".red(), error);
+                println!(
+                    "  {}",
+                    "fix the akash markers by hand or run: akash restore".dimmed()
+                );
+                drift_found = true;
+                continue;
+            }
+        };
+        let lines = span.lines(&content);
+
+        let found = block::split_entries(shell.as_ref(), &lines);
+        let expected: state::Entries = shell.generate_entries(&store).into_iter().collect();
+        let drifts = status::compare(
+            &expected,
+            &found.entries,
+            applied.entries_for(config_path)?.as_ref(),
+        );
+
+        if drifts.is_empty() && found.unrecognized.is_empty() {
+            println!("  {} ({} entries)", "in sync".green(), expected.len());
+            continue;
+        }
+        drift_found = true;
+        for (name, drift) in &drifts {
+            let label = format!("{:<9}", drift.label());
+            let label = match drift {
+                status::Drift::Missing | status::Drift::Stale | status::Drift::Removed => {
+                    label.yellow()
+                }
+                status::Drift::Modified | status::Drift::Added => label.red(),
+            };
+            println!("  {} {:<15} {}", label, name, drift.reason().dimmed());
+        }
+        for line in &found.unrecognized {
+            println!("  {} {}", format!("{:<9}", "unknown").red(), line);
+        }
+    }
+
+    if drift_found {
+        println!(
+            "\nRun {} to rewrite a block from the store, or {} to install a missing one \
+             (add --shell for another shell)",
+            "akash apply".cyan(),
+            "akash init".cyan()
+        );
+    } else {
+        println!("\n{} Everything is in sync", "OK!".green().bold());
+    }
+    Ok(drift_found)
+}
+
+/// Remove the managed block (inline aliases, or the source/hook line) from the
+/// config of every supported shell, then with `purge` delete ~/.akash.
+/// Returns whether anything was (or, with `dry_run`, would be) removed.
+fn cmd_uninstall(config: &Config, dry_run: bool, purge: bool) -> Result<bool> {
+    let backups = Backups::open_default()?;
+    let mut seen: Vec<PathBuf> = Vec::new();
+    let mut removed = false;
+    let mut failed = 0;
+
+    for shell_type in ShellType::ALL {
+        let shell = shell::get_shell(Some(shell_type))?;
+        let config_path = shell.config_path()?;
+        // sh and ksh can share the $ENV file
+        if seen.contains(&config_path) || !config_path.exists() {
+            continue;
+        }
+        seen.push(config_path.clone());
+
+        let content = std::fs::read_to_string(&config_path)
+            .with_context(|| format!("Failed to read {}", config_path.display()))?;
+        let new_content =
+            match block::remove_block(&content, &shell.begin_marker(), &shell.end_marker()) {
+                Ok(Some(new_content)) => new_content,
+                Ok(None) => continue,
+                Err(error) => {
+                    println!(
+                        "{} {}: {} (fix the akash markers by hand)",
+                        "Error:".red(),
+                        config_path.display(),
+                        error
+                    );
+                    failed += 1;
+                    continue;
+                }
+            };
+        removed = true;
+
+        if dry_run {
+            let label = config_path.display().to_string();
+            if let Some(diff) = diff::unified_diff(&content, &new_content, &label, true) {
+                println!("{}\n", diff);
+            }
+            continue;
+        }
+
+        // Backups live in ~/.akash, which --purge deletes
+        if !purge && let Some(record) = backups.create(&config_path, config.backup_retention)? {
+            println!(
+                "{}",
+                format!(
+                    "Backed up {} as {} (undo with: akash restore {})",
+                    config_path.display(),
+                    record.id,
+                    record.id
+                )
+                .dimmed()
+            );
+        }
+        fsutil::write_atomic(&config_path, new_content.as_bytes())
+            .with_context(|| format!("Failed to write {}", config_path.display()))?;
+        println!(
+            "{} akash block from {} ({})",
+            "Removed:".green(),
+            config_path.display(),
+            shell.name()
+        );
+    }
+
+    if !removed && failed == 0 {
+        println!("No akash block found in any shell config");
+    }
+
+    if purge {
+        let home =
+            dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Cannot determine home directory"))?;
+        let akash_dir = home.join(".akash");
+        if akash_dir.exists() {
+            removed = true;
+            if dry_run {
+                println!("{} {}", "Would delete:".yellow(), akash_dir.display());
+            } else {
+                std::fs::remove_dir_all(&akash_dir)
+                    .with_context(|| format!("Failed to delete {}", akash_dir.display()))?;
+                println!(
+                    "{} {} (aliases, config, backups)",
+                    "Deleted:".green(),
+                    akash_dir.display()
+                );
+            }
+        }
+        if let Some(custom) = &config.aliases_path {
+            println!(
+                "{} your alias store at {} was left in place",
+                "Note:".yellow(),
+                custom.display()
+            );
+        }
+    }
+
+    if dry_run && removed {
+        println!(
+            "{} nothing was changed (run without --dry-run to uninstall)",
+            "Dry run:".yellow().bold()
+        );
+    }
+    if failed > 0 {
+        anyhow::bail!("{} shell config(s) could not be cleaned up", failed);
+    }
+    Ok(removed)
+}
+
+/// Import the definitions found in a file, a shell's config, saved `alias` or `Get-Alias`
+/// output (also on stdin with `-`), or oh-my-zsh plugins, into the store.
+/// Returns whether anything is (or would be, with `dry_run`) imported.
+fn cmd_import(
+    config: &Config,
+    from: &str,
+    overwrite: bool,
+    yes: bool,
+    dry_run: bool,
+    strict: bool,
+) -> Result<bool> {
+    let mut store = AliasStore::store_load(config.aliases_path.as_ref())?;
+    let mut to_import: Vec<(&import::Found, &str)> = Vec::new();
+    let (mut found_count, mut skipped_count, mut conflicts, mut unchanged) = (0, 0, 0, 0);
+
+    let sources = import_sources(from)?;
+    for (label, parsed, syntax) in &sources {
+        let shell_key = syntax.shell_type().key();
+        found_count += parsed.found.len();
+        skipped_count += parsed.skipped.len();
+
+        println!("{} {}", "Importing from".bold(), label);
+        for found in &parsed.found {
+            let (current, shown) = match &found.definition {
+                import::Definition::Alias(command) => (
+                    store
+                        .list_aliases()
+                        .get(&found.name)
+                        .map(|entry| entry.command == *command),
+                    command.clone(),
+                ),
+                import::Definition::Function(body) => (
+                    store
+                        .list_functions()
+                        .get(&found.name)
+                        .map(|function| function.body_for(shell_key) == Some(body.as_str())),
+                    format!("function ({} lines)", body.lines().count()),
+                ),
+            };
+            // Aliases and functions share names, so either kind conflicts
+            let current = current.or_else(|| store.has_key(&found.name).then_some(false));
+            // A later file (e.g. plugin) redefines the name, as it would in the shell
+            to_import.retain(|(other, _)| other.name != found.name);
+            let note = found
+                .note
+                .map(|note| format!(" ({})", note).dimmed().to_string())
+                .unwrap_or_default();
+
+            match current {
+                Some(true) => {
+                    unchanged += 1;
+                    println!(
+                        "  {} {} -> {}{}",
+                        "=".dimmed(),
+                        found.name.dimmed(),
+                        shown.dimmed(),
+                        note
+                    );
+                }
+                Some(false) => {
+                    conflicts += 1;
+                    let action = if overwrite {
+                        "replaces existing"
+                    } else {
+                        "exists, kept (use --overwrite)"
+                    };
+                    println!(
+                        "  {} {} -> {}{} {}",
+                        "~".yellow(),
+                        found.name.bold(),
+                        shown,
+                        note,
+                        action.yellow()
+                    );
+                    if overwrite {
+                        to_import.push((found, shell_key));
+                    }
+                }
+                None => {
+                    println!(
+                        "  {} {} -> {}{}",
+                        "+".green(),
+                        found.name.bold(),
+                        shown,
+                        note
+                    );
+                    to_import.push((found, shell_key));
+                }
+            }
+        }
+
+        for skipped in &parsed.skipped {
+            println!(
+                "  {} line {}: {} ({})",
+                "!".red(),
+                skipped.line,
+                skipped.text,
+                skipped.reason.red()
+            );
+        }
+    }
+
     println!(
-        "Your aliases will be loaded when you open a new {} session.",
-        shell.name()
+        "\nFound {} definitions: {} to import, {} conflicting, {} already in the store, {} skipped",
+        found_count,
+        to_import.len(),
+        conflicts,
+        unchanged,
+        skipped_count
     );
+
+    if to_import.is_empty() {
+        return Ok(false);
+    }
+    let linter = Linter::new(&config.lint_allow);
+    let findings: Vec<Finding> = to_import
+        .iter()
+        .flat_map(|(found, _)| match &found.definition {
+            import::Definition::Alias(command) => linter.check(&found.name, command),
+            import::Definition::Function(body) => linter.check(&found.name, body),
+        })
+        .collect();
+    enforce_lint(strict, &findings)?;
+    if dry_run {
+        println!("{} nothing was imported", "Dry run:".yellow().bold());
+        return Ok(true);
+    }
+    if !yes && io::stdin().is_terminal() && !interactive::confirm("Import them?")? {
+        println!("Nothing imported");
+        return Ok(false);
+    }
+
+    for (found, shell_key) in &to_import {
+        match &found.definition {
+            import::Definition::Alias(command) => {
+                store.add_alias(found.name.clone(), command.clone());
+            }
+            import::Definition::Function(body) => {
+                store.add_function(found.name.clone(), body.clone(), Some(shell_key));
+            }
+        }
+    }
+    store.store_save(config.aliases_path.as_ref())?;
+
+    println!("{} {} definitions", "Imported:".green(), to_import.len());
+    if from != "-" {
+        println!(
+            "The originals are left in place: remove them once {} has written the akash block",
+            "akash apply".cyan()
+        );
+    }
+    Ok(true)
+}
+
+/// What `import --from` reads: labels, definitions found, and the syntax they follow.
+fn import_sources(from: &str) -> Result<Vec<(String, import::Parsed, import::Syntax)>> {
+    let zshrc = || -> Result<String> {
+        let path = shell::get_shell(Some(ShellType::Zsh))?.config_path()?;
+        Ok(std::fs::read_to_string(path).unwrap_or_default())
+    };
+    let plugin_sources = |dir: &Path| -> Result<Vec<(String, import::Parsed, import::Syntax)>> {
+        // Only the plugins enabled in ~/.zshrc, when it lists them
+        let enabled = import::enabled_plugins(&zshrc()?);
+        let files = import::plugin_files(dir, enabled.as_deref())?;
+        if files.is_empty() {
+            anyhow::bail!("No *.plugin.zsh files found in {}", dir.display());
+        }
+        files
+            .iter()
+            .map(|file| read_import_source(file, import::Syntax::Posix))
+            .collect()
+    };
+
+    if from == "-" {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .context("Failed to read aliases from stdin")?;
+        let syntax = import::Syntax::detect(&content, import::Syntax::Posix);
+        return Ok(vec![(
+            String::from("stdin"),
+            import::parse(&content, syntax),
+            syntax,
+        )]);
+    }
+
+    // An existing path wins over a shell name, so `--from ./bash` still reads the file
+    let path = Path::new(from);
+    if path.is_dir() {
+        return plugin_sources(path);
+    }
+    if path.exists() {
+        let syntax = import::Syntax::for_path(path);
+        return Ok(vec![read_import_source(path, syntax)?]);
+    }
+    if from == "oh-my-zsh" || from == "omz" {
+        let dir = match std::env::var_os("ZSH") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => dirs::home_dir()
+                .ok_or_else(|| anyhow::anyhow!("Cannot determine home directory"))?
+                .join(".oh-my-zsh"),
+        };
+        if !dir.is_dir() {
+            anyhow::bail!("oh-my-zsh not found in {} (set $ZSH)", dir.display());
+        }
+        return plugin_sources(&dir);
+    }
+    if let Ok(shell_type) = from.parse::<ShellType>() {
+        let syntax = import::Syntax::for_shell(shell_type)?;
+        let path = shell::get_shell(Some(shell_type))?.config_path()?;
+        return Ok(vec![read_import_source(&path, syntax)?]);
+    }
+    anyhow::bail!(
+        "'{}' is neither an existing file or directory, a known shell, nor oh-my-zsh",
+        from
+    )
+}
+
+/// Definitions in the file at `path`, outside the akash block.
+/// Saved `alias` or `Get-Alias` output is recognized whatever `syntax` says.
+fn read_import_source(
+    path: &Path,
+    syntax: import::Syntax,
+) -> Result<(String, import::Parsed, import::Syntax)> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let syntax = import::Syntax::detect(&content, syntax);
+
+    // akash's own block is skipped: those aliases are already in the store
+    let shell = shell::get_shell(Some(syntax.shell_type()))?;
+    let span = block::locate_block(&content, &shell.begin_marker(), &shell.end_marker())
+        .with_context(|| format!("Cannot tell the akash block apart in {}", path.display()))?;
+    let parsed = import::parse(&import::outside_block(&content, span.as_ref()), syntax);
+    Ok((path.display().to_string(), parsed, syntax))
+}
+
+fn cmd_export(
+    config: &Config,
+    format: Option<export::ExportFormat>,
+    output: Option<&Path>,
+) -> Result<()> {
+    let format = match (format, output) {
+        (Some(format), _) => format,
+        (None, Some(path)) => export::ExportFormat::for_path(path).with_context(|| {
+            format!(
+                "Cannot tell the format from {}: choose one with --format",
+                path.display()
+            )
+        })?,
+        (None, None) => anyhow::bail!("Choose a format with --format (e.g. --format yaml)"),
+    };
+
+    let store = AliasStore::store_load(config.aliases_path.as_ref())?;
+    let export = export::export(&store, format)?;
+
+    // Notes go to stderr, so stdout can be redirected to a file
+    for name in &export.skipped_functions {
+        eprintln!(
+            "{} function '{}' has no body for this shell, left out",
+            "Warning:".yellow(),
+            name
+        );
+    }
+
+    match output {
+        Some(path) => {
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+            }
+            fsutil::write_atomic(path, export.text.as_bytes())
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "{} {} aliases and {} functions to {}",
+                "Exported:".green(),
+                store.aliases.len(),
+                store.functions.len() - export.skipped_functions.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", export.text),
+    }
     Ok(())
 }
 
-// ============================================================================
-// HELPER: Block replacement
-// ============================================================================
+fn cmd_restore(config: &Config, shell: &dyn Shell, id: Option<&str>, list: bool) -> Result<()> {
+    let backups = Backups::open_default()?;
 
-/// Replace the akash block between markers, or append if not found.
-fn replace_or_append_block(
-    content: &str,
-    begin_marker: &str,
-    end_marker: &str,
-    new_block: &str,
-) -> String {
-    if let (Some(begin_pos), Some(end_pos)) = (content.find(begin_marker), content.find(end_marker))
-    {
-        // Found existing block -> replace it
-        let before = &content[..begin_pos];
-        let after_end = end_pos + end_marker.len();
-        let after = &content[after_end..];
-
-        let mut result = before.trim_end_matches('\n').to_string();
-        if !result.is_empty() {
-            result.push('\n');
-        }
-        result.push_str(new_block);
-        result.push_str(after);
-        result
-    } else {
-        // No existing block -> append
-        let mut result = content.to_string();
-        if !result.ends_with('\n') && !result.is_empty() {
-            result.push('\n');
+    if list {
+        let records = backups.list()?;
+        if records.is_empty() {
+            println!("No backups yet. akash saves one before each change to a shell config.");
+            return Ok(());
         }
-        if !result.is_empty() {
-            result.push('\n');
+        let max_len = records.iter().map(|r| r.id.len()).max().unwrap_or(0);
+        println!("{}", "Backups:".bold());
+        for record in records.iter().rev() {
+            println!(
+                "  {:width$}  {}  {}",
+                record.id.green(),
+                record.created_at.dimmed(),
+                record.source.display(),
+                width = max_len
+            );
         }
-        result.push_str(new_block);
-        result.push('\n');
-        result
+        return Ok(());
     }
+
+    let id = match id {
+        Some(id) => id.to_string(),
+        None => {
+            let config_path = shell.config_path()?;
+            backups
+                .latest_for(&config_path)?
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "No backup found for {}. See {}",
+                        config_path.display(),
+                        "akash restore --list"
+                    )
+                })?
+                .id
+        }
+    };
+
+    let record = backups.restore(&id, config.backup_retention)?;
+    println!(
+        "{} Restored {} from backup {}",
+        "Done!".green().bold(),
+        record.source.display(),
+        record.id.bold()
+    );
+    if shell.config_path().is_ok_and(|path| path == record.source) {
+        println!("{}", shell.reload_instructions().cyan());
+    }
+    Ok(())
 }
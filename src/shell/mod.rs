@@ -1,9 +1,10 @@
+mod nushell;
 mod unix;
 mod windows;
 
+use crate::store::AliasStore;
 use anyhow::{Result, anyhow};
-use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use sysinfo::{Pid, Process, System};
 use tracing::{debug, info, trace};
 
@@ -16,6 +17,9 @@ pub trait Shell {
     /// Shell display name (e.g., "PowerShell", "Bash", "Zsh")
     fn name(&self) -> &'static str;
 
+    /// Which of the supported shells this is
+    fn shell_type(&self) -> ShellType;
+
     /// Generate the alias syntax for this shell.
     /// e.g. Bash: `alias ll='ls -la'`
     /// e.g. PowerShell: `function ll { ls -la }`
@@ -27,11 +31,59 @@ pub trait Shell {
     /// Instructions to reload the shell config.
     fn reload_instructions(&self) -> String;
 
+    /// Generate a shell function for a parameterized alias.
+    /// e.g. Bash: `gco() { git checkout "$1"; }`
+    /// e.g. PowerShell: `function global:gco { param($p1) git checkout $p1 }`
+    fn function_syntax(&self, name: &str, segments: &[Segment]) -> String;
+
+    /// Generate the definition of one store entry: a function when the command
+    /// has placeholders, a plain alias otherwise.
+    fn entry_syntax(&self, name: &str, command: &str) -> String {
+        match parse_placeholders(command) {
+            Some(segments) => self.function_syntax(name, &segments),
+            None => self.alias_syntax(name, command),
+        }
+    }
+
+    /// Generate a multi-line shell function from a script body.
+    /// e.g. Bash: `name() {\n<body>\n}`
+    /// e.g. Fish: `function name\n<body>\nend`
+    fn function_block(&self, name: &str, body: &str) -> String;
+
     /// Comment prefix for this shell (default: "#")
     fn comment_prefix(&self) -> &'static str {
         "#"
     }
 
+    /// Extension of the generated alias file (e.g. "bash", "ps1")
+    fn script_extension(&self) -> &'static str;
+
+    /// Line that loads the file at `path` from the shell config, used by the
+    /// `source` apply mode. None when the shell cannot source a file.
+    /// e.g. PowerShell: `if (Test-Path 'path') { . 'path' }`
+    fn source_line(&self, path: &Path) -> Option<String>;
+
+    /// Line that evaluates the output of `akash hook` at startup, used by the
+    /// `hook` apply mode. None when the shell cannot evaluate generated code.
+    /// e.g. Bash: `eval "$(akash hook bash)"`
+    fn hook_line(&self) -> Option<String>;
+
+    /// Reserved words of the shell language, which cannot work as alias names
+    /// (e.g. `if`, `done`)
+    fn keywords(&self) -> &'static [&'static str];
+
+    /// Commands built into the shell, which an alias shadows (e.g. `cd`, `echo`)
+    fn builtins(&self) -> &'static [&'static str];
+
+    /// Generated alias file for the `source` apply mode: ~/.akash/generated/aliases.<ext>
+    fn generated_path(&self) -> Result<PathBuf> {
+        let home = dirs::home_dir().ok_or_else(|| anyhow!("Cannot determine home directory"))?;
+        Ok(home
+            .join(".akash")
+            .join("generated")
+            .join(format!("aliases.{}", self.script_extension())))
+    }
+
     /// Begin marker for the akash-managed block
     fn begin_marker(&self) -> String {
         format!("{} BEGIN akash aliases", self.comment_prefix())
@@ -42,18 +94,237 @@ pub trait Shell {
         format!("{} END akash aliases", self.comment_prefix())
     }
 
-    /// Generate the full alias block from a set of aliases.
-    fn generate_alias_block(&self, aliases: &BTreeMap<String, String>) -> String {
+    /// Name defined by a line of a generated block, when the line starts a definition.
+    /// Lines inside a multi-line function return None.
+    /// e.g. Bash: `alias gs='git status'` → "gs"
+    fn defined_name(&self, line: &str) -> Option<String>;
+
+    /// Blocks a line of a generated block opens minus the ones it closes, e.g. 1 for `f() {`.
+    /// Only lines outside every block can start a definition, so a function body that
+    /// contains `alias x=...` or a nested `g() {` stays with its function.
+    fn nesting(&self, line: &str) -> isize {
+        brace_nesting(line, '\\')
+    }
+
+    /// Generate the definition of every store entry, keyed by name.
+    /// Aliases come first so functions can use them.
+    fn generate_entries(&self, store: &AliasStore) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        for (name, entry) in &store.aliases {
+            entries.push((name.clone(), self.entry_syntax(name, &entry.command)));
+        }
+        for (name, function) in &store.functions {
+            match function.body_for(self.shell_type().key()) {
+                Some(body) => entries.push((name.clone(), self.function_block(name, body))),
+                None => debug!("Function '{}' has no body for {}", name, self.name()),
+            }
+        }
+        entries
+    }
+
+    /// Generate the full alias block from the store.
+    fn generate_alias_block(&self, store: &AliasStore) -> String {
         let mut lines = Vec::new();
         lines.push(self.begin_marker());
-        for (name, command) in aliases {
-            lines.push(self.alias_syntax(name, command));
-        }
+        lines.extend(
+            self.generate_entries(store)
+                .into_iter()
+                .map(|(_, text)| text),
+        );
         lines.push(self.end_marker());
         lines.join("\n")
     }
 }
 
+/// Alias name at the start of `text`, up to the first terminator.
+/// Used by `defined_name` implementations; None if it is not a valid alias name.
+fn name_before(text: &str, terminators: &[char]) -> Option<String> {
+    let end = text.find(terminators).unwrap_or(text.len());
+    let name = &text[..end];
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    valid.then(|| name.to_string())
+}
+
+/// `{` minus `}` outside quotes and comments; `escape` is the shell's escape character.
+fn brace_nesting(line: &str, escape: char) -> isize {
+    let mut nesting = 0;
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            // Nothing is escaped inside single quotes
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), _) => {}
+            (_, c) if c == escape => {
+                chars.next();
+            }
+            (Some(_), '"') => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '#') if previous.is_whitespace() => break,
+            (None, '{') => nesting += 1,
+            (None, '}') => nesting -= 1,
+            _ => {}
+        }
+        previous = c;
+    }
+    nesting
+}
+
+// ============================================================================
+// PARAMETERIZED ALIASES
+// ============================================================================
+
+/// A piece of a parameterized alias command.
+/// e.g. `git checkout {1}` → `[Text("git checkout "), Arg(1)]`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    /// Literal shell code, rendered as-is
+    Text(String),
+    /// `{N}`: the Nth argument (1-based)
+    Arg(usize),
+    /// `{@}`: all arguments
+    AllArgs,
+}
+
+/// Split a command on its `{N}` / `{@}` placeholders.
+/// Returns None when the command has no placeholder (plain alias).
+/// Any other brace (e.g. `${HOME}`, `{ $_ }`, `{0}`) is kept as literal text.
+pub fn parse_placeholders(command: &str) -> Option<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = command;
+    let mut found = false;
+
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let placeholder = after.find('}').and_then(|end| {
+            let inner = &after[..end];
+            let segment = match inner {
+                "@" => Some(Segment::AllArgs),
+                _ if !inner.is_empty() && inner.chars().all(|c| c.is_ascii_digit()) => {
+                    inner.parse().ok().filter(|n| *n > 0).map(Segment::Arg)
+                }
+                _ => None,
+            };
+            segment.map(|s| (s, end))
+        });
+
+        match placeholder {
+            Some((segment, end)) => {
+                text.push_str(&rest[..start]);
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(segment);
+                found = true;
+                rest = &after[end + 1..];
+            }
+            None => {
+                text.push_str(&rest[..=start]);
+                rest = after;
+            }
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+
+    found.then_some(segments)
+}
+
+/// Highest positional placeholder used, e.g. 2 for `cp {1} {2}`.
+pub fn max_arg(segments: &[Segment]) -> usize {
+    segments
+        .iter()
+        .filter_map(|s| match s {
+            Segment::Arg(n) => Some(*n),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Replace every call to the function's own name (`is_name`) in command position with
+/// `call`, so a function named after the command it wraps runs that command, not itself.
+/// e.g. `cd {1} && pwd` named `cd` → `command cd {1} && pwd`
+pub fn replace_self_calls(
+    segments: &[Segment],
+    is_name: impl Fn(&str) -> bool,
+    call: &str,
+) -> Vec<Segment> {
+    let mut command_start = true;
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => {
+                Segment::Text(replace_in_text(text, &is_name, call, &mut command_start))
+            }
+            other => {
+                command_start = false;
+                other.clone()
+            }
+        })
+        .collect()
+}
+
+fn replace_in_text(
+    text: &str,
+    is_name: &impl Fn(&str) -> bool,
+    call: &str,
+    command_start: &mut bool,
+) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut quote: Option<char> = None;
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if quote.is_none() && *command_start && !c.is_whitespace() && c != '{' {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || ";|&(){}<>".contains(c))
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            if !word.is_empty() {
+                result.push_str(if is_name(word) { call } else { word });
+                rest = &rest[end..];
+                // `if cd ..`, `! cd`, `and cd` (fish): the next word is still a command
+                *command_start = matches!(
+                    word,
+                    "if" | "then"
+                        | "else"
+                        | "elif"
+                        | "do"
+                        | "while"
+                        | "until"
+                        | "!"
+                        | "and"
+                        | "or"
+                        | "not"
+                        | "begin"
+                        | "time"
+                );
+                continue;
+            }
+        }
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ';' | '|' | '&' | '(' | '{' | '\n') => *command_start = true,
+            _ => {}
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    result
+}
+
 /// All shells that akash supports
 #[derive(Debug, Clone, Copy, PartialEq)]
 //       ↑      ↑      ↑      ↑
@@ -65,7 +336,11 @@ pub enum ShellType {
     Bash,
     Zsh,
     PowerShell,
-    // Fish,  // For later
+    Fish,
+    Nushell,
+    Cmd,
+    Sh,
+    Ksh,
 }
 
 /// Display trait: how to print ShellType as a user-friendly string
@@ -75,6 +350,39 @@ impl std::fmt::Display for ShellType {
             ShellType::Bash => write!(formatter, "Bash Shell"),
             ShellType::Zsh => write!(formatter, "Zsh Shell"),
             ShellType::PowerShell => write!(formatter, "PowerShell Shell"),
+            ShellType::Fish => write!(formatter, "Fish Shell"),
+            ShellType::Nushell => write!(formatter, "Nushell Shell"),
+            ShellType::Cmd => write!(formatter, "Cmd Shell"),
+            ShellType::Sh => write!(formatter, "POSIX Shell"),
+            ShellType::Ksh => write!(formatter, "Korn Shell"),
+        }
+    }
+}
+
+impl ShellType {
+    /// Every supported shell
+    pub const ALL: [ShellType; 8] = [
+        ShellType::Bash,
+        ShellType::Zsh,
+        ShellType::PowerShell,
+        ShellType::Fish,
+        ShellType::Nushell,
+        ShellType::Cmd,
+        ShellType::Sh,
+        ShellType::Ksh,
+    ];
+
+    /// Stable lowercase identifier, used as a key in the alias store
+    pub fn key(&self) -> &'static str {
+        match self {
+            ShellType::Bash => "bash",
+            ShellType::Zsh => "zsh",
+            ShellType::PowerShell => "powershell",
+            ShellType::Fish => "fish",
+            ShellType::Nushell => "nushell",
+            ShellType::Cmd => "cmd",
+            ShellType::Sh => "sh",
+            ShellType::Ksh => "ksh",
         }
     }
 }
@@ -95,8 +403,13 @@ impl std::str::FromStr for ShellType {
             "bash" | "git-bash" => Ok(Self::Bash),
             "zsh" => Ok(Self::Zsh),
             "powershell" | "pwsh" => Ok(Self::PowerShell),
+            "fish" => Ok(Self::Fish),
+            "nu" | "nushell" => Ok(Self::Nushell),
+            "cmd" | "cmd.exe" => Ok(Self::Cmd),
+            "sh" | "dash" | "ash" | "posix" => Ok(Self::Sh),
+            "ksh" | "mksh" | "ksh93" => Ok(Self::Ksh),
             _ => anyhow::bail!(
-                "Unsupported shell: '{}'. Supported: bash, zsh, powershell",
+                "Unsupported shell: '{}'. Supported: bash, zsh, fish, nushell, powershell, cmd, sh, ksh",
                 input
             ),
         }
@@ -156,11 +469,38 @@ fn detect_from_parent_process() -> Option<ShellType> {
         debug!("Detected PowerShell from parent process");
         return Some(ShellType::PowerShell);
     }
+    if parent_lower.contains("fish") {
+        debug!("Detected Fish from parent process");
+        return Some(ShellType::Fish);
+    }
+    // "nu" is too short for a substring match (e.g. "runuser"), so compare exactly
+    if parent_lower == "nu" || parent_lower == "nu.exe" {
+        debug!("Detected Nushell from parent process");
+        return Some(ShellType::Nushell);
+    }
+    if parent_lower == "cmd" || parent_lower == "cmd.exe" {
+        debug!("Detected Cmd from parent process");
+        return Some(ShellType::Cmd);
+    }
+    // Matches ksh, ksh93, mksh, pdksh
+    if parent_lower.contains("ksh") {
+        debug!("Detected Ksh from parent process");
+        return Some(ShellType::Ksh);
+    }
+    if is_posix_sh_name(&parent_lower) {
+        debug!("Detected POSIX sh from parent process");
+        return Some(ShellType::Sh);
+    }
 
     debug!("Unknown parent process: {}", parent_name);
     None
 }
 
+/// Plain POSIX shells. Compared exactly: "sh" is a substring of most shell names.
+fn is_posix_sh_name(name: &str) -> bool {
+    matches!(name, "sh" | "dash" | "ash" | "busybox")
+}
+
 // ============================================================================
 // DETECTION: Environment Variables
 // ============================================================================
@@ -180,6 +520,23 @@ fn detect_from_env() -> Option<ShellType> {
             debug!("Detected Bash from $SHELL");
             return Some(ShellType::Bash);
         }
+        if shell_lower.contains("fish") {
+            debug!("Detected Fish from $SHELL");
+            return Some(ShellType::Fish);
+        }
+        if shell_lower.ends_with("/nu") {
+            debug!("Detected Nushell from $SHELL");
+            return Some(ShellType::Nushell);
+        }
+        if shell_lower.contains("ksh") {
+            debug!("Detected Ksh from $SHELL");
+            return Some(ShellType::Ksh);
+        }
+        let shell_file = shell_lower.rsplit('/').next().unwrap_or_default();
+        if is_posix_sh_name(shell_file) {
+            debug!("Detected POSIX sh from $SHELL");
+            return Some(ShellType::Sh);
+        }
     }
 
     // Check PowerShell-specific variable
@@ -197,6 +554,14 @@ fn detect_from_env() -> Option<ShellType> {
         debug!("Detected Zsh from $ZSH_VERSION");
         return Some(ShellType::Zsh);
     }
+    if std::env::var("FISH_VERSION").is_ok() {
+        debug!("Detected Fish from $FISH_VERSION");
+        return Some(ShellType::Fish);
+    }
+    if std::env::var("NU_VERSION").is_ok() {
+        debug!("Detected Nushell from $NU_VERSION");
+        return Some(ShellType::Nushell);
+    }
 
     debug!("Could not detect shell from environment");
     None
@@ -272,5 +637,68 @@ pub fn get_shell(override_shell: Option<ShellType>) -> Result<Box<dyn Shell>> {
         ShellType::Bash => Ok(Box::new(unix::Bash)),
         ShellType::Zsh => Ok(Box::new(unix::Zsh)),
         ShellType::PowerShell => Ok(Box::new(windows::PowerShell)),
+        ShellType::Fish => Ok(Box::new(unix::Fish)),
+        ShellType::Nushell => Ok(Box::new(nushell::Nushell)),
+        ShellType::Cmd => Ok(Box::new(windows::Cmd)),
+        ShellType::Sh => Ok(Box::new(unix::Sh)),
+        ShellType::Ksh => Ok(Box::new(unix::Ksh)),
+    }
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // --- parse_placeholders ---
+
+    #[test]
+    fn given_plain_command_when_parsing_placeholders_then_returns_none() {
+        // Given
+        let command = "git status";
+
+        // When
+        let segments = parse_placeholders(command);
+
+        // Then
+        assert_eq!(segments, None);
+    }
+
+    #[test]
+    fn given_positional_and_all_args_when_parsing_placeholders_then_splits_segments() {
+        // Given
+        let command = "git checkout {1} {@}";
+
+        // When
+        let segments = parse_placeholders(command);
+
+        // Then
+        assert_eq!(
+            segments,
+            Some(vec![
+                Segment::Text("git checkout ".into()),
+                Segment::Arg(1),
+                Segment::Text(" ".into()),
+                Segment::AllArgs,
+            ])
+        );
+    }
+
+    #[test]
+    fn given_shell_braces_when_parsing_placeholders_then_they_stay_literal() {
+        // Given
+        let command = "echo ${HOME} {0} { x } {2}";
+
+        // When
+        let segments = parse_placeholders(command);
+
+        // Then
+        assert_eq!(
+            segments,
+            Some(vec![
+                Segment::Text("echo ${HOME} {0} { x } ".into()),
+                Segment::Arg(2)
+            ])
+        );
     }
 }
@@ -0,0 +1,383 @@
+use super::{Segment, Shell, ShellType, max_arg, name_before, replace_self_calls};
+use anyhow::Result;
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+
+pub struct Nushell;
+
+const NUSHELL_KEYWORDS: &[&str] = &[
+    "alias", "break", "const", "continue", "def", "do", "else", "export", "extern", "for", "hide",
+    "if", "let", "loop", "match", "module", "mut", "overlay", "return", "source", "try", "use",
+    "where", "while",
+];
+
+/// Common built-in commands: Nushell replaces many coreutils with its own
+const NUSHELL_BUILTINS: &[&str] = &[
+    "cd", "clear", "cp", "du", "each", "echo", "exit", "find", "first", "get", "glob", "help",
+    "history", "kill", "last", "length", "ls", "mkdir", "mv", "open", "print", "ps", "rm", "save",
+    "select", "sleep", "sort", "sort-by", "start", "sys", "table", "touch", "uniq", "which",
+];
+
+/// Prefix of the alias that keeps a built-in reachable once a def shadows it
+const CORE_PREFIX: &str = "akash-core-";
+
+impl Shell for Nushell {
+    fn name(&self) -> &'static str {
+        "Nushell"
+    }
+
+    fn shell_type(&self) -> ShellType {
+        ShellType::Nushell
+    }
+
+    fn alias_syntax(&self, name: &str, command: &str) -> String {
+        // Nushell aliases must expand to a single command: no pipelines or statements.
+        // For those, we define a custom command that hands its arguments to the first one.
+        if first_command_end(command) == command.len() {
+            // An alias is expanded where it is defined: `ls` in `alias ls = ls -la` is the built-in
+            return format!("alias {} = {}", name, command);
+        }
+
+        let body = DefBody::new(name, &[Segment::Text(command.to_string())]);
+        let command: String = body
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.as_str(),
+                _ => "",
+            })
+            .collect();
+        let (head, tail) = command.split_at(first_command_end(&command));
+        body.render(
+            name,
+            true,
+            "...rest",
+            &format!("{} ...$rest {}", head.trim_end(), tail),
+        )
+    }
+
+    fn function_syntax(&self, name: &str, segments: &[Segment]) -> String {
+        // Positional placeholders become parameters p1..pN;
+        // {@} needs a rest parameter, which --wrapped passes through untouched.
+        let mut params: Vec<String> = (1..=max_arg(segments)).map(|n| format!("p{}", n)).collect();
+        let wrapped = segments.contains(&Segment::AllArgs);
+        if wrapped {
+            params.push(String::from("...rest"));
+        }
+
+        let body = DefBody::new(name, segments);
+        let text: String = body
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Arg(n) => format!("$p{}", n),
+                Segment::AllArgs => String::from("...$rest"),
+            })
+            .collect();
+        body.render(name, wrapped, &params.join(", "), &text)
+    }
+
+    fn function_block(&self, name: &str, body: &str) -> String {
+        // Arguments are reachable from the body as $args
+        let flag = if runs_cd(&[Segment::Text(body.to_string())]) {
+            "--env "
+        } else {
+            ""
+        };
+        format!(
+            "def {}--wrapped {} [...args] {{\n{}\n}}",
+            flag,
+            name,
+            body.trim_end()
+        )
+    }
+
+    fn defined_name(&self, line: &str) -> Option<String> {
+        // `alias NAME = ...`, `def NAME [...]` or `def --env --wrapped NAME [...]`
+        // The `alias akash-core-NAME = NAME` line of a shadowed built-in belongs to the def after it
+        let rest = match line.strip_prefix("def ") {
+            Some(rest) => rest
+                .trim_start_matches("--env ")
+                .trim_start_matches("--wrapped "),
+            None => line
+                .strip_prefix("alias ")
+                .map(|rest| rest.strip_prefix(CORE_PREFIX).unwrap_or(rest))?,
+        };
+        name_before(rest, &[' ', '['])
+    }
+
+    fn script_extension(&self) -> &'static str {
+        "nu"
+    }
+
+    fn source_line(&self, path: &Path) -> Option<String> {
+        // `source` is resolved at parse time, so it cannot be guarded:
+        // apply writes the generated file before the line is installed
+        let escaped = path
+            .display()
+            .to_string()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"");
+        Some(format!("source \"{}\"", escaped))
+    }
+
+    fn hook_line(&self) -> Option<String> {
+        // Nushell parses its config before running anything, so it cannot eval output
+        None
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        NUSHELL_KEYWORDS
+    }
+
+    fn builtins(&self) -> &'static [&'static str] {
+        NUSHELL_BUILTINS
+    }
+
+    fn config_path(&self) -> Result<PathBuf> {
+        // Same directory as $nu.default-config-dir:
+        // $XDG_CONFIG_HOME/nushell, or the platform config dir (e.g. ~/.config/nushell)
+        let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => dirs::config_dir()
+                .ok_or_else(|| anyhow::anyhow!("Cannot determine config directory"))?,
+        };
+        Ok(config_dir.join("nushell").join("config.nu"))
+    }
+
+    fn reload_instructions(&self) -> String {
+        String::from("Restart your terminal or run: source $nu.config-path")
+    }
+}
+
+/// Body of a `def` named `name`, with its calls to `name` qualified: a def calls itself
+/// otherwise. `^name` runs the external command; a shadowed built-in is reached through
+/// an alias taken before the def, which Nushell resolves where it is defined.
+struct DefBody {
+    segments: Vec<Segment>,
+    /// `alias akash-core-NAME = NAME`, needed before the def
+    core_alias: Option<String>,
+    /// The body runs `cd`, which only reaches the caller from a `def --env`
+    env: bool,
+}
+
+impl DefBody {
+    fn new(name: &str, original: &[Segment]) -> Self {
+        let builtin = NUSHELL_BUILTINS.contains(&name);
+        let call = if builtin {
+            format!("{}{}", CORE_PREFIX, name)
+        } else {
+            format!("^{}", name)
+        };
+        let calls_itself = Cell::new(false);
+        let segments = replace_self_calls(
+            original,
+            |word| {
+                calls_itself.set(calls_itself.get() || word == name);
+                word == name
+            },
+            &call,
+        );
+        let core_alias = (builtin && calls_itself.get())
+            .then(|| format!("alias {}{} = {}", CORE_PREFIX, name, name));
+        Self {
+            env: runs_cd(original),
+            segments,
+            core_alias,
+        }
+    }
+
+    /// `def [--env] [--wrapped] name [params] { text }`, after the core alias if any.
+    fn render(&self, name: &str, wrapped: bool, params: &str, text: &str) -> String {
+        let mut flags = String::new();
+        if self.env {
+            flags.push_str("--env ");
+        }
+        if wrapped {
+            flags.push_str("--wrapped ");
+        }
+        let def = format!("def {}{} [{}] {{ {} }}", flags, name, params, text);
+        match &self.core_alias {
+            Some(alias) => format!("{}\n{}", alias, def),
+            None => def,
+        }
+    }
+}
+
+/// Whether one of the commands in `segments` is `cd`.
+fn runs_cd(segments: &[Segment]) -> bool {
+    let found = Cell::new(false);
+    replace_self_calls(
+        segments,
+        |word| {
+            found.set(found.get() || word == "cd");
+            false
+        },
+        "",
+    );
+    found.get()
+}
+
+/// Byte offset of the first `|` or `;` outside strings and blocks, or the length of `command`.
+fn first_command_end(command: &str) -> usize {
+    let mut depth = 0usize;
+    let mut chars = command.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => {
+                // Backslash escapes inside double quotes only
+                while let Some((_, next)) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '\'' | '`' => {
+                for (_, next) in chars.by_ref() {
+                    if next == c {
+                        break;
+                    }
+                }
+            }
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth = depth.saturating_sub(1),
+            '|' | ';' if depth == 0 => return index,
+            _ => {}
+        }
+    }
+    command.len()
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // --- alias_syntax ---
+
+    #[test]
+    fn given_single_command_when_generating_alias_then_uses_alias() {
+        // Given
+        let shell = Nushell;
+
+        // When
+        let line = shell.alias_syntax("gs", "git status");
+
+        // Then
+        assert_eq!(line, "alias gs = git status");
+    }
+
+    #[test]
+    fn given_pipeline_when_generating_alias_then_uses_def() {
+        // Given
+        let shell = Nushell;
+
+        // When
+        let line = shell.alias_syntax("big", "ls | where size > 1mb");
+
+        // Then
+        assert_eq!(
+            line,
+            "def --wrapped big [...rest] { ls ...$rest | where size > 1mb }"
+        );
+    }
+
+    #[test]
+    fn given_quoted_pipe_when_generating_alias_then_keeps_alias() {
+        // Given
+        let shell = Nushell;
+
+        // When
+        let line = shell.alias_syntax("bar", "echo 'a|b' \"c;d\"");
+
+        // Then
+        assert_eq!(line, "alias bar = echo 'a|b' \"c;d\"");
+    }
+
+    // --- function_syntax ---
+
+    #[test]
+    fn given_placeholders_when_generating_entry_then_uses_def_parameters() {
+        // Given
+        let shell = Nushell;
+
+        // When
+        let line = shell.entry_syntax("gco", "git checkout {1} {@}");
+
+        // Then
+        assert_eq!(
+            line,
+            "def --wrapped gco [p1, ...rest] { git checkout $p1 ...$rest }"
+        );
+    }
+
+    #[test]
+    fn given_placeholder_wrapper_named_after_its_command_when_generating_entry_then_no_recursion() {
+        // Given
+        let shell = Nushell;
+
+        // When
+        let external = shell.entry_syntax("git", "git {1} --verbose");
+        let builtin = shell.entry_syntax("ls", "ls {1}");
+        let pipeline = shell.alias_syntax("ls", "ls -la | sort-by size");
+
+        // Then
+        assert_eq!(external, "def git [p1] { ^git $p1 --verbose }");
+        assert_eq!(
+            builtin,
+            "alias akash-core-ls = ls\ndef ls [p1] { akash-core-ls $p1 }"
+        );
+        assert_eq!(
+            pipeline,
+            "alias akash-core-ls = ls\n\
+             def --wrapped ls [...rest] { akash-core-ls -la ...$rest | sort-by size }"
+        );
+        assert_eq!(
+            shell.defined_name("alias akash-core-ls = ls"),
+            Some(String::from("ls"))
+        );
+    }
+
+    #[test]
+    fn given_body_running_cd_when_generating_def_then_uses_env_flag() {
+        // Given
+        let shell = Nushell;
+
+        // When
+        let pipeline = shell.alias_syntax("proj", "cd ~/src | ls");
+        let placeholder = shell.entry_syntax("cd", "cd {1}");
+        let block = shell.function_block("up", "cd ..\nls");
+
+        // Then
+        assert_eq!(
+            pipeline,
+            "def --env --wrapped proj [...rest] { cd ~/src ...$rest | ls }"
+        );
+        assert_eq!(
+            placeholder,
+            "alias akash-core-cd = cd\ndef --env cd [p1] { akash-core-cd $p1 }"
+        );
+        assert_eq!(block, "def --env --wrapped up [...args] {\ncd ..\nls\n}");
+        assert_eq!(shell.defined_name(&pipeline), Some(String::from("proj")));
+    }
+
+    // --- source_line ---
+
+    #[test]
+    fn given_windows_path_when_generating_source_line_then_escapes_backslashes() {
+        // Given
+        let path = Path::new(r"C:\Users\u\aliases.nu");
+
+        // When
+        let line = Nushell.source_line(path).unwrap();
+
+        // Then
+        assert_eq!(line, r#"source "C:\\Users\\u\\aliases.nu""#);
+    }
+}
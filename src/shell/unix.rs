@@ -1,18 +1,517 @@
-use super::Shell;
+use super::{Segment, Shell, ShellType, name_before, replace_self_calls};
 use anyhow::Result;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct Bash;
 pub struct Zsh;
+pub struct Fish;
+/// Generic POSIX shell: sh, dash, busybox ash
+pub struct Sh;
+pub struct Ksh;
+
+/// Reserved words of the POSIX shell grammar
+const POSIX_KEYWORDS: &[&str] = &[
+    "!", "{", "}", "case", "do", "done", "elif", "else", "esac", "fi", "for", "if", "in", "then",
+    "until", "while",
+];
+
+/// Special and regular builtins of POSIX sh (dash, busybox ash)
+const POSIX_BUILTINS: &[&str] = &[
+    ".", ":", "alias", "bg", "break", "cd", "command", "continue", "echo", "eval", "exec", "exit",
+    "export", "false", "fg", "getopts", "hash", "jobs", "kill", "local", "printf", "pwd", "read",
+    "readonly", "return", "set", "shift", "test", "times", "trap", "true", "type", "ulimit",
+    "umask", "unalias", "unset", "wait", "[",
+];
+
+const BASH_KEYWORDS: &[&str] = &[
+    "!", "{", "}", "[[", "]]", "case", "coproc", "do", "done", "elif", "else", "esac", "fi", "for",
+    "function", "if", "in", "select", "then", "time", "until", "while",
+];
+
+const BASH_BUILTINS: &[&str] = &[
+    ".",
+    ":",
+    "[",
+    "alias",
+    "bg",
+    "bind",
+    "break",
+    "builtin",
+    "caller",
+    "cd",
+    "command",
+    "compgen",
+    "complete",
+    "compopt",
+    "continue",
+    "declare",
+    "dirs",
+    "disown",
+    "echo",
+    "enable",
+    "eval",
+    "exec",
+    "exit",
+    "export",
+    "false",
+    "fc",
+    "fg",
+    "getopts",
+    "hash",
+    "help",
+    "history",
+    "jobs",
+    "kill",
+    "let",
+    "local",
+    "logout",
+    "mapfile",
+    "popd",
+    "printf",
+    "pushd",
+    "pwd",
+    "read",
+    "readarray",
+    "readonly",
+    "return",
+    "set",
+    "shift",
+    "shopt",
+    "source",
+    "suspend",
+    "test",
+    "times",
+    "trap",
+    "true",
+    "type",
+    "typeset",
+    "ulimit",
+    "umask",
+    "unalias",
+    "unset",
+    "wait",
+];
+
+const ZSH_KEYWORDS: &[&str] = &[
+    "!",
+    "{",
+    "}",
+    "[[",
+    "]]",
+    "case",
+    "coproc",
+    "do",
+    "done",
+    "elif",
+    "else",
+    "end",
+    "esac",
+    "fi",
+    "for",
+    "foreach",
+    "function",
+    "if",
+    "in",
+    "nocorrect",
+    "noglob",
+    "repeat",
+    "select",
+    "then",
+    "time",
+    "until",
+    "while",
+];
+
+const ZSH_BUILTINS: &[&str] = &[
+    ".",
+    ":",
+    "[",
+    "alias",
+    "autoload",
+    "bg",
+    "bindkey",
+    "break",
+    "builtin",
+    "bye",
+    "cd",
+    "chdir",
+    "command",
+    "compdef",
+    "continue",
+    "declare",
+    "dirs",
+    "disable",
+    "disown",
+    "echo",
+    "emulate",
+    "enable",
+    "eval",
+    "exec",
+    "exit",
+    "export",
+    "false",
+    "fc",
+    "fg",
+    "float",
+    "functions",
+    "getopts",
+    "hash",
+    "history",
+    "integer",
+    "jobs",
+    "kill",
+    "let",
+    "local",
+    "logout",
+    "popd",
+    "print",
+    "printf",
+    "pushd",
+    "pushln",
+    "pwd",
+    "r",
+    "read",
+    "readonly",
+    "rehash",
+    "return",
+    "sched",
+    "set",
+    "setopt",
+    "shift",
+    "source",
+    "suspend",
+    "test",
+    "times",
+    "trap",
+    "true",
+    "type",
+    "typeset",
+    "ulimit",
+    "umask",
+    "unalias",
+    "unfunction",
+    "unhash",
+    "unset",
+    "unsetopt",
+    "vared",
+    "wait",
+    "whence",
+    "where",
+    "which",
+    "zle",
+    "zmodload",
+    "zstyle",
+];
+
+const FISH_KEYWORDS: &[&str] = &[
+    "and", "begin", "break", "case", "continue", "else", "end", "for", "function", "if", "in",
+    "not", "or", "return", "switch", "while",
+];
+
+const FISH_BUILTINS: &[&str] = &[
+    ".",
+    ":",
+    "[",
+    "abbr",
+    "argparse",
+    "bg",
+    "bind",
+    "block",
+    "builtin",
+    "cd",
+    "command",
+    "commandline",
+    "complete",
+    "contains",
+    "count",
+    "disown",
+    "echo",
+    "emit",
+    "eval",
+    "exec",
+    "exit",
+    "false",
+    "fg",
+    "functions",
+    "history",
+    "jobs",
+    "math",
+    "printf",
+    "pwd",
+    "random",
+    "read",
+    "realpath",
+    "set",
+    "set_color",
+    "source",
+    "status",
+    "string",
+    "test",
+    "time",
+    "true",
+    "type",
+    "ulimit",
+    "wait",
+];
+
+const KSH_KEYWORDS: &[&str] = &[
+    "!", "{", "}", "[[", "]]", "case", "do", "done", "elif", "else", "esac", "fi", "for",
+    "function", "if", "in", "select", "then", "time", "until", "while",
+];
+
+const KSH_BUILTINS: &[&str] = &[
+    ".",
+    ":",
+    "[",
+    "alias",
+    "autoload",
+    "bg",
+    "break",
+    "builtin",
+    "cd",
+    "command",
+    "continue",
+    "echo",
+    "eval",
+    "exec",
+    "exit",
+    "export",
+    "false",
+    "fc",
+    "fg",
+    "functions",
+    "getopts",
+    "hash",
+    "history",
+    "integer",
+    "jobs",
+    "kill",
+    "let",
+    "print",
+    "printf",
+    "pwd",
+    "read",
+    "readonly",
+    "return",
+    "set",
+    "shift",
+    "test",
+    "times",
+    "trap",
+    "true",
+    "type",
+    "typeset",
+    "ulimit",
+    "umask",
+    "unalias",
+    "unset",
+    "wait",
+    "whence",
+];
+
+/// POSIX alias syntax shared by every sh-compatible shell.
+fn posix_alias(name: &str, command: &str) -> String {
+    // Escape single quotes: replace ' with '\''
+    format!("alias {}='{}'", name, command.replace("'", "'\\''"))
+}
+
+/// Line dropping an alias of the same name before a function is defined: bash and zsh
+/// expand it inside the `name() {` header when the config is sourced again.
+/// It must be on a line of its own, since a whole line is parsed before any of it runs.
+fn posix_unalias(name: &str) -> String {
+    format!("unalias {} 2>/dev/null || true", name)
+}
+
+/// POSIX function for a parameterized alias: `gco() { git checkout "$1"; }`,
+/// after `posix_unalias`. A call to its own name skips functions, otherwise `cd` →
+/// `cd {1} && pwd` would call itself forever: one of `builtins` goes through `builtin`
+/// (zsh's `command` never runs builtins), anything else through `command`.
+/// sh and ksh pass no builtins: their `command` runs builtins and `builtin` does not.
+fn posix_function(name: &str, segments: &[Segment], builtins: &[&str]) -> String {
+    let call = if builtins.contains(&name) {
+        format!("builtin {}", name)
+    } else {
+        format!("command {}", name)
+    };
+    let body: String = replace_self_calls(segments, |word| word == name, &call)
+        .iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => text.clone(),
+            Segment::Arg(n) if *n < 10 => format!("\"${}\"", n),
+            Segment::Arg(n) => format!("\"${{{}}}\"", n),
+            Segment::AllArgs => String::from("\"$@\""),
+        })
+        .collect();
+    format!("{}\n{}() {{ {}; }}", posix_unalias(name), name, body)
+}
+
+/// POSIX multi-line function, after `posix_unalias`. The body is kept verbatim
+/// (no indentation) so heredoc delimiters stay at the start of their line.
+fn posix_function_block(name: &str, body: &str) -> String {
+    format!(
+        "{}\n{}() {{\n{}\n}}",
+        posix_unalias(name),
+        name,
+        body.trim_end()
+    )
+}
+
+/// POSIX source line, guarded so a deleted generated file does not break the shell.
+fn posix_source_line(path: &Path) -> String {
+    format!("if [ -f {0} ]; then . {0}; fi", posix_quote_path(path))
+}
+
+/// `path` as a single-quoted POSIX word.
+fn posix_quote_path(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace("'", "'\\''"))
+}
+
+/// `. <config file>` for the file akash writes, or `fallback` if it cannot be located.
+fn posix_reload_command(config_path: Result<PathBuf>, fallback: &str) -> String {
+    match config_path {
+        Ok(path) => format!(". {}", posix_quote_path(&path)),
+        Err(_) => format!(". {}", fallback),
+    }
+}
+
+/// Name defined by a POSIX block line: `alias NAME='...'`, `NAME() {`,
+/// or the `unalias NAME` line that comes before a function
+fn posix_defined_name(line: &str) -> Option<String> {
+    if let Some(rest) = line.strip_prefix("unalias ") {
+        return name_before(rest, &[' ']);
+    }
+    match line.strip_prefix("alias ") {
+        Some(rest) => name_before(rest, &['=']),
+        None => line
+            .contains("() {")
+            .then(|| name_before(line, &['(']))
+            .flatten(),
+    }
+}
+
+/// fish blocks open with a keyword and close with `end`: count the statements of `line`
+/// (split on `;` outside quotes, up to a comment) that start with one or the other.
+fn fish_nesting(line: &str) -> isize {
+    let mut statements = vec![String::new()];
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            // fish honours \' and \\ even inside single quotes
+            (_, '\\') => {
+                chars.next();
+            }
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '#')
+                if statements
+                    .last()
+                    .is_none_or(|statement| statement.is_empty() || statement.ends_with(' ')) =>
+            {
+                break;
+            }
+            (None, ';') => {
+                statements.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        if let Some(statement) = statements.last_mut() {
+            statement.push(c);
+        }
+    }
+
+    statements
+        .iter()
+        .map(
+            |statement| match statement.split_whitespace().next().unwrap_or_default() {
+                "function" | "if" | "for" | "while" | "switch" | "begin" => 1,
+                "end" => -1,
+                _ => 0,
+            },
+        )
+        .sum()
+}
+
+/// `path` as a single-quoted fish word: only `\\` and `\'` are escapes inside.
+fn fish_quote_path(path: &Path) -> String {
+    let escaped = path
+        .display()
+        .to_string()
+        .replace('\\', "\\\\")
+        .replace('\'', "\\'");
+    format!("'{}'", escaped)
+}
+
+/// Path from `$ENV` (the file interactive POSIX shells source), if set.
+/// Only a leading `~` or `$HOME` is expanded, since we cannot evaluate arbitrary parameters.
+fn env_file_path() -> Option<PathBuf> {
+    let value = std::env::var("ENV").ok().filter(|v| !v.trim().is_empty())?;
+    let home = dirs::home_dir()?;
+
+    if let Some(rest) = value.strip_prefix("~/") {
+        return Some(home.join(rest));
+    }
+    for prefix in ["$HOME/", "${HOME}/"] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return Some(home.join(rest));
+        }
+    }
+    if value.contains('$') {
+        // Unexpandable parameter: fall back to the default file
+        return None;
+    }
+    Some(PathBuf::from(value))
+}
 
 impl Shell for Bash {
     fn name(&self) -> &'static str {
         "Bash"
     }
 
+    fn shell_type(&self) -> ShellType {
+        ShellType::Bash
+    }
+
     fn alias_syntax(&self, name: &str, command: &str) -> String {
-        // Escape single quotes: replace ' with '\''
-        format!("alias {}='{}'", name, command.replace("'", "'\\''"))
+        posix_alias(name, command)
+    }
+
+    fn function_syntax(&self, name: &str, segments: &[Segment]) -> String {
+        posix_function(name, segments, BASH_BUILTINS)
+    }
+
+    fn function_block(&self, name: &str, body: &str) -> String {
+        posix_function_block(name, body)
+    }
+
+    fn defined_name(&self, line: &str) -> Option<String> {
+        posix_defined_name(line)
+    }
+
+    fn script_extension(&self) -> &'static str {
+        "bash"
+    }
+
+    fn source_line(&self, path: &Path) -> Option<String> {
+        Some(posix_source_line(path))
+    }
+
+    fn hook_line(&self) -> Option<String> {
+        Some(format!(
+            "eval \"$(akash hook {})\"",
+            self.shell_type().key()
+        ))
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        BASH_KEYWORDS
+    }
+
+    fn builtins(&self) -> &'static [&'static str] {
+        BASH_BUILTINS
     }
 
     fn config_path(&self) -> Result<PathBuf> {
@@ -31,8 +530,47 @@ impl Shell for Zsh {
         "Zsh"
     }
 
+    fn shell_type(&self) -> ShellType {
+        ShellType::Zsh
+    }
+
     fn alias_syntax(&self, name: &str, command: &str) -> String {
-        format!("alias {}='{}'", name, command.replace("'", "'\\''"))
+        posix_alias(name, command)
+    }
+
+    fn function_syntax(&self, name: &str, segments: &[Segment]) -> String {
+        posix_function(name, segments, ZSH_BUILTINS)
+    }
+
+    fn function_block(&self, name: &str, body: &str) -> String {
+        posix_function_block(name, body)
+    }
+
+    fn defined_name(&self, line: &str) -> Option<String> {
+        posix_defined_name(line)
+    }
+
+    fn script_extension(&self) -> &'static str {
+        "zsh"
+    }
+
+    fn source_line(&self, path: &Path) -> Option<String> {
+        Some(posix_source_line(path))
+    }
+
+    fn hook_line(&self) -> Option<String> {
+        Some(format!(
+            "eval \"$(akash hook {})\"",
+            self.shell_type().key()
+        ))
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        ZSH_KEYWORDS
+    }
+
+    fn builtins(&self) -> &'static [&'static str] {
+        ZSH_BUILTINS
     }
 
     fn config_path(&self) -> Result<PathBuf> {
@@ -45,3 +583,538 @@ impl Shell for Zsh {
         String::from("Restart your terminal or run: source ~/.zshrc")
     }
 }
+
+impl Shell for Fish {
+    fn name(&self) -> &'static str {
+        "Fish"
+    }
+
+    fn shell_type(&self) -> ShellType {
+        ShellType::Fish
+    }
+
+    fn alias_syntax(&self, name: &str, command: &str) -> String {
+        // Inside fish single quotes, only \\ and \' are escape sequences
+        let escaped = command.replace('\\', "\\\\").replace('\'', "\\'");
+        format!("alias {} '{}'", name, escaped)
+    }
+
+    fn function_syntax(&self, name: &str, segments: &[Segment]) -> String {
+        // $argv holds the arguments; fish lists are 1-based like {N}.
+        // A call to its own name goes to the builtin or the executable, not back here
+        let call = if FISH_BUILTINS.contains(&name) {
+            format!("builtin {}", name)
+        } else {
+            format!("command {}", name)
+        };
+        let body: String = replace_self_calls(segments, |word| word == name, &call)
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Arg(n) => format!("$argv[{}]", n),
+                Segment::AllArgs => String::from("$argv"),
+            })
+            .collect();
+        format!("function {}; {}; end", name, body)
+    }
+
+    fn function_block(&self, name: &str, body: &str) -> String {
+        format!("function {}\n{}\nend", name, body.trim_end())
+    }
+
+    fn defined_name(&self, line: &str) -> Option<String> {
+        // `alias NAME '...'` or `function NAME` / `function NAME; ...; end`
+        let rest = line
+            .strip_prefix("alias ")
+            .or_else(|| line.strip_prefix("function "))?;
+        name_before(rest, &[' ', ';'])
+    }
+
+    fn nesting(&self, line: &str) -> isize {
+        fish_nesting(line)
+    }
+
+    fn script_extension(&self) -> &'static str {
+        "fish"
+    }
+
+    fn source_line(&self, path: &Path) -> Option<String> {
+        Some(format!(
+            "test -f {0}; and source {0}",
+            fish_quote_path(path)
+        ))
+    }
+
+    fn hook_line(&self) -> Option<String> {
+        Some(String::from("akash hook fish | source"))
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        FISH_KEYWORDS
+    }
+
+    fn builtins(&self) -> &'static [&'static str] {
+        FISH_BUILTINS
+    }
+
+    fn config_path(&self) -> Result<PathBuf> {
+        // Fish sources every file in conf.d on startup, so akash gets its own file
+        // and never has to touch config.fish: ~/.config/fish/conf.d/akash.fish
+        let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => dirs::home_dir()
+                .ok_or_else(|| anyhow::anyhow!("Cannot determine home directory"))?
+                .join(".config"),
+        };
+        Ok(config_dir.join("fish").join("conf.d").join("akash.fish"))
+    }
+
+    fn reload_instructions(&self) -> String {
+        // Same directory as config_path: XDG_CONFIG_HOME may move it
+        let path = match self.config_path() {
+            Ok(path) => fish_quote_path(&path),
+            Err(_) => String::from("~/.config/fish/conf.d/akash.fish"),
+        };
+        format!("Restart your terminal or run: source {}", path)
+    }
+}
+
+impl Shell for Sh {
+    fn name(&self) -> &'static str {
+        "Sh"
+    }
+
+    fn shell_type(&self) -> ShellType {
+        ShellType::Sh
+    }
+
+    fn alias_syntax(&self, name: &str, command: &str) -> String {
+        posix_alias(name, command)
+    }
+
+    fn function_syntax(&self, name: &str, segments: &[Segment]) -> String {
+        posix_function(name, segments, &[])
+    }
+
+    fn function_block(&self, name: &str, body: &str) -> String {
+        posix_function_block(name, body)
+    }
+
+    fn defined_name(&self, line: &str) -> Option<String> {
+        posix_defined_name(line)
+    }
+
+    fn script_extension(&self) -> &'static str {
+        "sh"
+    }
+
+    fn source_line(&self, path: &Path) -> Option<String> {
+        Some(posix_source_line(path))
+    }
+
+    fn hook_line(&self) -> Option<String> {
+        Some(format!(
+            "eval \"$(akash hook {})\"",
+            self.shell_type().key()
+        ))
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        POSIX_KEYWORDS
+    }
+
+    fn builtins(&self) -> &'static [&'static str] {
+        POSIX_BUILTINS
+    }
+
+    fn config_path(&self) -> Result<PathBuf> {
+        // Interactive sh/dash/ash read $ENV; login shells read ~/.profile
+        if let Some(path) = env_file_path() {
+            return Ok(path);
+        }
+        let home =
+            dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Cannot determine home directory"))?;
+        Ok(home.join(".profile"))
+    }
+
+    fn reload_instructions(&self) -> String {
+        let reload = format!(
+            "Log in again or run: {}",
+            posix_reload_command(self.config_path(), "~/.profile")
+        );
+        if env_file_path().is_some() {
+            // config_path is the file ENV already names
+            return reload;
+        }
+        format!(
+            "{}\nFor non-login shells, make sure ENV points to it: export ENV=\"$HOME/.profile\"",
+            reload
+        )
+    }
+}
+
+impl Shell for Ksh {
+    fn name(&self) -> &'static str {
+        "Ksh"
+    }
+
+    fn shell_type(&self) -> ShellType {
+        ShellType::Ksh
+    }
+
+    fn alias_syntax(&self, name: &str, command: &str) -> String {
+        posix_alias(name, command)
+    }
+
+    fn function_syntax(&self, name: &str, segments: &[Segment]) -> String {
+        posix_function(name, segments, &[])
+    }
+
+    fn function_block(&self, name: &str, body: &str) -> String {
+        posix_function_block(name, body)
+    }
+
+    fn defined_name(&self, line: &str) -> Option<String> {
+        posix_defined_name(line)
+    }
+
+    fn script_extension(&self) -> &'static str {
+        "ksh"
+    }
+
+    fn source_line(&self, path: &Path) -> Option<String> {
+        Some(posix_source_line(path))
+    }
+
+    fn hook_line(&self) -> Option<String> {
+        Some(format!(
+            "eval \"$(akash hook {})\"",
+            self.shell_type().key()
+        ))
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        KSH_KEYWORDS
+    }
+
+    fn builtins(&self) -> &'static [&'static str] {
+        KSH_BUILTINS
+    }
+
+    fn config_path(&self) -> Result<PathBuf> {
+        // ksh reads $ENV, and ~/.kshrc when ENV is unset (ksh93, mksh)
+        if let Some(path) = env_file_path() {
+            return Ok(path);
+        }
+        let home =
+            dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Cannot determine home directory"))?;
+        Ok(home.join(".kshrc"))
+    }
+
+    fn reload_instructions(&self) -> String {
+        format!(
+            "Restart your terminal or run: {}",
+            posix_reload_command(self.config_path(), "~/.kshrc")
+        )
+    }
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `script` with bash, if installed: (success, stdout)
+    fn run_bash(script: &str) -> Option<(bool, String)> {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        let mut child = Command::new("bash")
+            .args(["--norc", "--noprofile"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .ok()?;
+        child.stdin.take()?.write_all(script.as_bytes()).ok()?;
+        let output = child.wait_with_output().ok()?;
+        Some((
+            output.status.success(),
+            String::from_utf8_lossy(&output.stdout).to_string(),
+        ))
+    }
+
+    // --- posix_alias ---
+
+    #[test]
+    fn given_single_quotes_when_generating_posix_alias_then_they_are_escaped() {
+        // Given
+        let shell = Sh;
+
+        // When
+        let line = shell.alias_syntax("hi", "echo 'hello'");
+
+        // Then
+        assert_eq!(line, r"alias hi='echo '\''hello'\'''");
+    }
+
+    // --- posix_function ---
+
+    #[test]
+    fn given_placeholders_when_generating_bash_entry_then_renders_function() {
+        // Given
+        let shell = Bash;
+
+        // When
+        let line = shell.entry_syntax("gco", "git checkout {1} {@}");
+
+        // Then
+        assert_eq!(
+            line,
+            "unalias gco 2>/dev/null || true\ngco() { git checkout \"$1\" \"$@\"; }"
+        );
+    }
+
+    #[test]
+    fn given_alias_turned_placeholder_function_when_sourcing_again_then_bash_accepts_it() {
+        // Given
+        let shell = Bash;
+        let entry = shell.entry_syntax("gco", "echo new {1}");
+        // The previous apply defined gco as an alias, still present in the session
+        let script = format!(
+            "shopt -s expand_aliases\nalias gco='echo old'\n{}\ngco main\n",
+            entry
+        );
+
+        // When
+        let output = run_bash(&script);
+
+        // Then
+        assert_eq!(
+            entry,
+            "unalias gco 2>/dev/null || true\ngco() { echo new \"$1\"; }"
+        );
+        if let Some((success, stdout)) = output {
+            assert!(success);
+            assert_eq!(stdout, "new main\n");
+        }
+    }
+
+    #[test]
+    fn given_plain_command_when_generating_bash_entry_then_renders_alias() {
+        // Given
+        let shell = Bash;
+
+        // When
+        let line = shell.entry_syntax("gs", "git status");
+
+        // Then
+        assert_eq!(line, "alias gs='git status'");
+    }
+
+    // --- posix_function_block ---
+
+    #[test]
+    fn given_multiline_body_when_generating_bash_function_block_then_wraps_body_verbatim() {
+        // Given
+        let shell = Bash;
+
+        // When
+        let block = shell.function_block("mkcd", "mkdir -p \"$1\"\ncd \"$1\"\n");
+
+        // Then
+        assert_eq!(
+            block,
+            "unalias mkcd 2>/dev/null || true\nmkcd() {\nmkdir -p \"$1\"\ncd \"$1\"\n}"
+        );
+    }
+
+    #[test]
+    fn given_function_replacing_alias_when_sourcing_again_then_bash_accepts_it() {
+        // Given
+        let shell = Bash;
+        let block = shell.function_block("greet", "echo hello \"$1\"\necho bye");
+        let script = format!(
+            "shopt -s expand_aliases\nalias greet='echo old'\n{}\ngreet you\n",
+            block
+        );
+
+        // When
+        let output = run_bash(&script);
+
+        // Then
+        assert!(block.starts_with("unalias greet 2>/dev/null || true\ngreet() {\n"));
+        if let Some((success, stdout)) = output {
+            assert!(success);
+            assert_eq!(stdout, "hello you\nbye\n");
+        }
+    }
+
+    #[test]
+    fn given_self_calling_placeholder_alias_when_running_then_calls_the_real_command() {
+        // Given
+        let shell = Bash;
+        let line = shell.entry_syntax("cd", "cd {1} && pwd");
+        let script = format!("{}\ncd /\n", line);
+
+        // When
+        let output = run_bash(&script);
+
+        // Then
+        assert!(line.ends_with("cd() { builtin cd \"$1\" && pwd; }"));
+        if let Some((success, stdout)) = output {
+            assert!(success);
+            assert_eq!(stdout, "/\n");
+        }
+    }
+
+    #[test]
+    fn given_self_calling_builtin_when_generating_zsh_entry_then_uses_builtin() {
+        // Given
+        let shell = Zsh;
+
+        // When
+        let line = shell.entry_syntax("cd", "cd {1}");
+
+        // Then
+        assert_eq!(
+            line,
+            "unalias cd 2>/dev/null || true\ncd() { builtin cd \"$1\"; }"
+        );
+    }
+
+    #[test]
+    fn given_self_calling_builtin_when_generating_sh_entry_then_uses_command() {
+        // Given
+        let shell = Sh;
+
+        // When
+        let line = shell.entry_syntax("cd", "cd {1}");
+
+        // Then
+        assert!(line.ends_with("cd() { command cd \"$1\"; }"));
+    }
+
+    #[test]
+    fn given_own_name_as_argument_when_generating_function_then_only_calls_are_qualified() {
+        // Given
+        let shell = Zsh;
+
+        // When
+        let line = shell.entry_syntax("git", "echo git; git {1} 'git'");
+
+        // Then
+        assert!(line.ends_with("git() { echo git; command git \"$1\" 'git'; }"));
+    }
+
+    // --- Fish::function_syntax ---
+
+    #[test]
+    fn given_self_calling_placeholder_alias_when_generating_fish_entry_then_skips_the_function() {
+        // Given
+        let shell = Fish;
+
+        // When
+        let builtin = shell.entry_syntax("cd", "cd {1}; and ls");
+        let external = shell.entry_syntax("grep", "grep --color {@}");
+
+        // Then
+        assert_eq!(builtin, "function cd; builtin cd $argv[1]; and ls; end");
+        assert_eq!(external, "function grep; command grep --color $argv; end");
+    }
+
+    #[test]
+    fn given_placeholders_when_generating_fish_entry_then_uses_argv() {
+        // Given
+        let shell = Fish;
+
+        // When
+        let line = shell.entry_syntax("mvto", "mv {@} {1}");
+
+        // Then
+        assert_eq!(line, "function mvto; mv $argv $argv[1]; end");
+    }
+
+    // --- Fish::alias_syntax ---
+
+    #[test]
+    fn given_simple_command_when_generating_fish_alias_then_uses_single_quotes() {
+        // Given
+        let shell = Fish;
+
+        // When
+        let line = shell.alias_syntax("ll", "ls -la");
+
+        // Then
+        assert_eq!(line, "alias ll 'ls -la'");
+    }
+
+    #[test]
+    fn given_quotes_and_backslashes_when_generating_fish_alias_then_they_are_escaped() {
+        // Given
+        let shell = Fish;
+
+        // When
+        let line = shell.alias_syntax("greet", r"echo 'hi' C:\tmp");
+
+        // Then
+        assert_eq!(line, r"alias greet 'echo \'hi\' C:\\tmp'");
+    }
+
+    // --- source_line ---
+
+    #[test]
+    fn given_path_with_quote_when_generating_source_line_then_guards_and_escapes() {
+        // Given
+        let path = Path::new("/home/o'neil/.akash/generated/aliases.bash");
+
+        // When
+        let line = Bash.source_line(path).unwrap();
+
+        // Then
+        assert_eq!(
+            line,
+            r"if [ -f '/home/o'\''neil/.akash/generated/aliases.bash' ]; then . '/home/o'\''neil/.akash/generated/aliases.bash'; fi"
+        );
+    }
+
+    #[test]
+    fn given_path_when_generating_fish_source_line_then_uses_and_source() {
+        // Given
+        let path = Path::new("/home/u/.akash/generated/aliases.fish");
+
+        // When
+        let line = Fish.source_line(path).unwrap();
+
+        // Then
+        assert_eq!(
+            line,
+            "test -f '/home/u/.akash/generated/aliases.fish'; and source '/home/u/.akash/generated/aliases.fish'"
+        );
+    }
+
+    // --- hook_line ---
+
+    #[test]
+    fn given_posix_shells_when_generating_hook_line_then_evals_hook_output() {
+        // Given / When / Then
+        assert_eq!(Bash.hook_line().unwrap(), r#"eval "$(akash hook bash)""#);
+        assert_eq!(Ksh.hook_line().unwrap(), r#"eval "$(akash hook ksh)""#);
+        assert_eq!(Fish.hook_line().unwrap(), "akash hook fish | source");
+    }
+
+    // --- reload_instructions ---
+
+    #[test]
+    fn given_shells_writing_their_own_file_when_getting_reload_instructions_then_name_it() {
+        // Given
+        let shells: [&dyn Shell; 3] = [&Fish, &Sh, &Ksh];
+
+        // When / Then
+        for shell in shells {
+            let path = shell.config_path().unwrap().display().to_string();
+            let text = shell.reload_instructions();
+            assert!(text.contains(&format!("'{}'", path)), "{}", text);
+        }
+    }
+}
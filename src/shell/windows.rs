@@ -1,22 +1,196 @@
-use super::Shell;
+use super::{Segment, Shell, ShellType, brace_nesting, max_arg, name_before, replace_self_calls};
 use anyhow::Result;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct PowerShell;
+pub struct Cmd;
+
+const POWERSHELL_KEYWORDS: &[&str] = &[
+    "begin",
+    "break",
+    "catch",
+    "class",
+    "continue",
+    "data",
+    "do",
+    "dynamicparam",
+    "else",
+    "elseif",
+    "end",
+    "enum",
+    "exit",
+    "filter",
+    "finally",
+    "for",
+    "foreach",
+    "function",
+    "if",
+    "in",
+    "param",
+    "process",
+    "return",
+    "switch",
+    "throw",
+    "trap",
+    "try",
+    "until",
+    "using",
+    "while",
+];
+
+/// Internal commands of cmd.exe
+const CMD_BUILTINS: &[&str] = &[
+    "assoc", "break", "call", "cd", "chdir", "cls", "color", "copy", "date", "del", "dir", "echo",
+    "endlocal", "erase", "exit", "for", "ftype", "goto", "if", "md", "mkdir", "mklink", "move",
+    "path", "pause", "popd", "prompt", "pushd", "rd", "rem", "ren", "rename", "rmdir", "set",
+    "setlocal", "shift", "start", "time", "title", "type", "ver", "verify", "vol",
+];
 
 impl Shell for PowerShell {
     fn name(&self) -> &'static str {
         "PowerShell"
     }
 
+    fn shell_type(&self) -> ShellType {
+        ShellType::PowerShell
+    }
+
     fn alias_syntax(&self, name: &str, command: &str) -> String {
-        // Set-Alias only works for simple command->command (no args).
-        // For commands with arguments/pipes, we use a function wrapper.
-        if command.contains(' ') || command.contains('|') || command.contains(';') {
-            format!("function {} {{ {} }}", name, command)
+        let mut lines = Vec::new();
+
+        // Built-in aliases win over functions and most are AllScope/ReadOnly,
+        // so they have to be removed before we can redefine the name.
+        let collides = is_builtin_alias(name);
+        if collides {
+            lines.push(format!(
+                "Remove-Item -Path Alias:{} -Force -ErrorAction SilentlyContinue",
+                name
+            ));
+        }
+
+        if is_bare_command(command) {
+            // Set-Alias only works for simple command->command (no args)
+            let force = if collides { " -Force" } else { "" };
+            lines.push(format!(
+                "Set-Alias -Name {} -Value {} -Scope Global{}",
+                name, command, force
+            ));
+        } else {
+            // For commands with arguments/pipes, we use a function wrapper.
+            // `ls` -> `ls -la` must not call the function it defines
+            let command: String = replace_self_calls(
+                &[Segment::Text(command.to_string())],
+                |word| word.eq_ignore_ascii_case(name),
+                &powershell_self_call(name),
+            )
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.as_str(),
+                _ => "",
+            })
+            .collect();
+            lines.push(format!(
+                "function global:{} {{ {} }}",
+                name,
+                powershell_body(&command)
+            ));
+        }
+        lines.join("\n")
+    }
+
+    fn function_syntax(&self, name: &str, segments: &[Segment]) -> String {
+        let mut lines = Vec::new();
+        if is_builtin_alias(name) {
+            lines.push(format!(
+                "Remove-Item -Path Alias:{} -Force -ErrorAction SilentlyContinue",
+                name
+            ));
+        }
+
+        // {N} binds to param($pN); anything beyond the declared params stays in $args,
+        // so {@} re-emits the bound params before splatting the rest.
+        let params: Vec<String> = (1..=max_arg(segments))
+            .map(|n| format!("$p{}", n))
+            .collect();
+        let call = powershell_self_call(name);
+        let body: String =
+            replace_self_calls(segments, |word| word.eq_ignore_ascii_case(name), &call)
+                .iter()
+                .map(|segment| match segment {
+                    Segment::Text(text) => text.clone(),
+                    Segment::Arg(n) => format!("$p{}", n),
+                    Segment::AllArgs if params.is_empty() => String::from("@args"),
+                    Segment::AllArgs => format!("{} @args", params.join(" ")),
+                })
+                .collect();
+
+        if params.is_empty() {
+            lines.push(format!("function global:{} {{ {} }}", name, body));
         } else {
-            format!("Set-Alias -Name {} -Value {}", name, command)
+            lines.push(format!(
+                "function global:{} {{ param({}) {} }}",
+                name,
+                params.join(", "),
+                body
+            ));
         }
+        lines.join("\n")
+    }
+
+    fn function_block(&self, name: &str, body: &str) -> String {
+        let mut lines = Vec::new();
+        if is_builtin_alias(name) {
+            lines.push(format!(
+                "Remove-Item -Path Alias:{} -Force -ErrorAction SilentlyContinue",
+                name
+            ));
+        }
+        // Arguments are reachable from the body as $args
+        lines.push(format!(
+            "function global:{} {{\n{}\n}}",
+            name,
+            body.trim_end()
+        ));
+        lines.join("\n")
+    }
+
+    fn defined_name(&self, line: &str) -> Option<String> {
+        // The Remove-Item line of a shadowed built-in belongs to the definition after it
+        let rest = line
+            .strip_prefix("Remove-Item -Path Alias:")
+            .or_else(|| line.strip_prefix("Set-Alias -Name "))
+            .or_else(|| line.strip_prefix("function global:"))?;
+        name_before(rest, &[' '])
+    }
+
+    fn nesting(&self, line: &str) -> isize {
+        // Backtick is PowerShell's escape character
+        brace_nesting(line, '`')
+    }
+
+    fn script_extension(&self) -> &'static str {
+        "ps1"
+    }
+
+    fn source_line(&self, path: &Path) -> Option<String> {
+        // Dot-sourcing runs the file in the profile's scope
+        let quoted = powershell_single_quote(&path.display().to_string());
+        Some(format!("if (Test-Path '{0}') {{ . '{0}' }}", quoted))
+    }
+
+    fn hook_line(&self) -> Option<String> {
+        Some(String::from(
+            "akash hook powershell | Out-String | Invoke-Expression",
+        ))
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        POWERSHELL_KEYWORDS
+    }
+
+    fn builtins(&self) -> &'static [&'static str] {
+        // Cmdlets have Verb-Noun names; the short names an alias shadows are built-in aliases
+        &BUILTIN_ALIASES
     }
 
     fn config_path(&self) -> Result<PathBuf> {
@@ -33,3 +207,922 @@ impl Shell for PowerShell {
         String::from("Restart PowerShell or run: . $PROFILE")
     }
 }
+
+/// Aliases that ship with PowerShell (5.1 and 7+) and can collide with user aliases,
+/// with the command each one stands for (module-qualified for cmdlets).
+const BUILTIN_ALIAS_TARGETS: &[(&str, &str)] = &[
+    ("%", r"Microsoft.PowerShell.Core\ForEach-Object"),
+    ("?", r"Microsoft.PowerShell.Core\Where-Object"),
+    ("ac", r"Microsoft.PowerShell.Management\Add-Content"),
+    ("cat", r"Microsoft.PowerShell.Management\Get-Content"),
+    ("cd", r"Microsoft.PowerShell.Management\Set-Location"),
+    ("chdir", r"Microsoft.PowerShell.Management\Set-Location"),
+    ("clc", r"Microsoft.PowerShell.Management\Clear-Content"),
+    ("clear", "Clear-Host"),
+    ("clhy", r"Microsoft.PowerShell.Core\Clear-History"),
+    ("cli", r"Microsoft.PowerShell.Management\Clear-Item"),
+    ("clp", r"Microsoft.PowerShell.Management\Clear-ItemProperty"),
+    ("cls", "Clear-Host"),
+    ("clv", r"Microsoft.PowerShell.Utility\Clear-Variable"),
+    ("cnsn", r"Microsoft.PowerShell.Core\Connect-PSSession"),
+    ("compare", r"Microsoft.PowerShell.Utility\Compare-Object"),
+    ("copy", r"Microsoft.PowerShell.Management\Copy-Item"),
+    ("cp", r"Microsoft.PowerShell.Management\Copy-Item"),
+    ("cpi", r"Microsoft.PowerShell.Management\Copy-Item"),
+    ("cpp", r"Microsoft.PowerShell.Management\Copy-ItemProperty"),
+    ("curl", r"Microsoft.PowerShell.Utility\Invoke-WebRequest"),
+    ("cvpa", r"Microsoft.PowerShell.Management\Convert-Path"),
+    ("dbp", r"Microsoft.PowerShell.Utility\Disable-PSBreakpoint"),
+    ("del", r"Microsoft.PowerShell.Management\Remove-Item"),
+    ("diff", r"Microsoft.PowerShell.Utility\Compare-Object"),
+    ("dir", r"Microsoft.PowerShell.Management\Get-ChildItem"),
+    ("dnsn", r"Microsoft.PowerShell.Core\Disconnect-PSSession"),
+    ("ebp", r"Microsoft.PowerShell.Utility\Enable-PSBreakpoint"),
+    ("echo", r"Microsoft.PowerShell.Utility\Write-Output"),
+    ("epal", r"Microsoft.PowerShell.Utility\Export-Alias"),
+    ("epcsv", r"Microsoft.PowerShell.Utility\Export-Csv"),
+    ("epsn", r"Microsoft.PowerShell.Utility\Export-PSSession"),
+    ("erase", r"Microsoft.PowerShell.Management\Remove-Item"),
+    ("etsn", r"Microsoft.PowerShell.Core\Enter-PSSession"),
+    ("exsn", r"Microsoft.PowerShell.Core\Exit-PSSession"),
+    ("fc", r"Microsoft.PowerShell.Utility\Format-Custom"),
+    ("fhx", r"Microsoft.PowerShell.Utility\Format-Hex"),
+    ("fl", r"Microsoft.PowerShell.Utility\Format-List"),
+    ("foreach", r"Microsoft.PowerShell.Core\ForEach-Object"),
+    ("ft", r"Microsoft.PowerShell.Utility\Format-Table"),
+    ("fw", r"Microsoft.PowerShell.Utility\Format-Wide"),
+    ("gal", r"Microsoft.PowerShell.Utility\Get-Alias"),
+    ("gbp", r"Microsoft.PowerShell.Utility\Get-PSBreakpoint"),
+    ("gc", r"Microsoft.PowerShell.Management\Get-Content"),
+    ("gcb", r"Microsoft.PowerShell.Management\Get-Clipboard"),
+    ("gci", r"Microsoft.PowerShell.Management\Get-ChildItem"),
+    ("gcm", r"Microsoft.PowerShell.Core\Get-Command"),
+    ("gcs", r"Microsoft.PowerShell.Utility\Get-PSCallStack"),
+    ("gdr", r"Microsoft.PowerShell.Management\Get-PSDrive"),
+    ("ghy", r"Microsoft.PowerShell.Core\Get-History"),
+    ("gi", r"Microsoft.PowerShell.Management\Get-Item"),
+    ("gin", r"Microsoft.PowerShell.Management\Get-ComputerInfo"),
+    ("gjb", r"Microsoft.PowerShell.Core\Get-Job"),
+    ("gl", r"Microsoft.PowerShell.Management\Get-Location"),
+    ("gm", r"Microsoft.PowerShell.Utility\Get-Member"),
+    ("gmo", r"Microsoft.PowerShell.Core\Get-Module"),
+    ("gp", r"Microsoft.PowerShell.Management\Get-ItemProperty"),
+    ("gps", r"Microsoft.PowerShell.Management\Get-Process"),
+    (
+        "gpv",
+        r"Microsoft.PowerShell.Management\Get-ItemPropertyValue",
+    ),
+    ("group", r"Microsoft.PowerShell.Utility\Group-Object"),
+    ("gsn", r"Microsoft.PowerShell.Core\Get-PSSession"),
+    ("gsnp", r"Microsoft.PowerShell.Core\Get-PSSnapin"),
+    ("gsv", r"Microsoft.PowerShell.Management\Get-Service"),
+    ("gtz", r"Microsoft.PowerShell.Management\Get-TimeZone"),
+    ("gu", r"Microsoft.PowerShell.Utility\Get-Unique"),
+    ("gv", r"Microsoft.PowerShell.Utility\Get-Variable"),
+    ("gwmi", r"Microsoft.PowerShell.Management\Get-WmiObject"),
+    ("h", r"Microsoft.PowerShell.Core\Get-History"),
+    ("history", r"Microsoft.PowerShell.Core\Get-History"),
+    ("icm", r"Microsoft.PowerShell.Core\Invoke-Command"),
+    ("iex", r"Microsoft.PowerShell.Utility\Invoke-Expression"),
+    ("ihy", r"Microsoft.PowerShell.Core\Invoke-History"),
+    ("ii", r"Microsoft.PowerShell.Management\Invoke-Item"),
+    ("ipal", r"Microsoft.PowerShell.Utility\Import-Alias"),
+    ("ipcsv", r"Microsoft.PowerShell.Utility\Import-Csv"),
+    ("ipmo", r"Microsoft.PowerShell.Core\Import-Module"),
+    ("ipsn", r"Microsoft.PowerShell.Utility\Import-PSSession"),
+    ("irm", r"Microsoft.PowerShell.Utility\Invoke-RestMethod"),
+    ("ise", "powershell_ise.exe"),
+    ("iwmi", r"Microsoft.PowerShell.Management\Invoke-WmiMethod"),
+    ("iwr", r"Microsoft.PowerShell.Utility\Invoke-WebRequest"),
+    ("kill", r"Microsoft.PowerShell.Management\Stop-Process"),
+    ("lp", r"Microsoft.PowerShell.Utility\Out-Printer"),
+    ("ls", r"Microsoft.PowerShell.Management\Get-ChildItem"),
+    ("man", "help"),
+    ("md", "mkdir"),
+    ("measure", r"Microsoft.PowerShell.Utility\Measure-Object"),
+    ("mi", r"Microsoft.PowerShell.Management\Move-Item"),
+    ("mount", r"Microsoft.PowerShell.Management\New-PSDrive"),
+    ("move", r"Microsoft.PowerShell.Management\Move-Item"),
+    ("mp", r"Microsoft.PowerShell.Management\Move-ItemProperty"),
+    ("mv", r"Microsoft.PowerShell.Management\Move-Item"),
+    ("nal", r"Microsoft.PowerShell.Utility\New-Alias"),
+    ("ndr", r"Microsoft.PowerShell.Management\New-PSDrive"),
+    ("ni", r"Microsoft.PowerShell.Management\New-Item"),
+    ("nmo", r"Microsoft.PowerShell.Core\New-Module"),
+    (
+        "npssc",
+        r"Microsoft.PowerShell.Core\New-PSSessionConfigurationFile",
+    ),
+    ("nsn", r"Microsoft.PowerShell.Core\New-PSSession"),
+    ("nv", r"Microsoft.PowerShell.Utility\New-Variable"),
+    ("ogv", r"Microsoft.PowerShell.Utility\Out-GridView"),
+    ("oh", r"Microsoft.PowerShell.Core\Out-Host"),
+    ("popd", r"Microsoft.PowerShell.Management\Pop-Location"),
+    ("ps", r"Microsoft.PowerShell.Management\Get-Process"),
+    ("pushd", r"Microsoft.PowerShell.Management\Push-Location"),
+    ("pwd", r"Microsoft.PowerShell.Management\Get-Location"),
+    ("r", r"Microsoft.PowerShell.Core\Invoke-History"),
+    ("rbp", r"Microsoft.PowerShell.Utility\Remove-PSBreakpoint"),
+    ("rcjb", r"Microsoft.PowerShell.Core\Receive-Job"),
+    ("rcsn", r"Microsoft.PowerShell.Core\Receive-PSSession"),
+    ("rd", r"Microsoft.PowerShell.Management\Remove-Item"),
+    ("rdr", r"Microsoft.PowerShell.Management\Remove-PSDrive"),
+    ("ren", r"Microsoft.PowerShell.Management\Rename-Item"),
+    ("ri", r"Microsoft.PowerShell.Management\Remove-Item"),
+    ("rjb", r"Microsoft.PowerShell.Core\Remove-Job"),
+    ("rm", r"Microsoft.PowerShell.Management\Remove-Item"),
+    ("rmdir", r"Microsoft.PowerShell.Management\Remove-Item"),
+    ("rmo", r"Microsoft.PowerShell.Core\Remove-Module"),
+    ("rni", r"Microsoft.PowerShell.Management\Rename-Item"),
+    (
+        "rnp",
+        r"Microsoft.PowerShell.Management\Rename-ItemProperty",
+    ),
+    ("rp", r"Microsoft.PowerShell.Management\Remove-ItemProperty"),
+    ("rsn", r"Microsoft.PowerShell.Core\Remove-PSSession"),
+    ("rsnp", r"Microsoft.PowerShell.Core\Remove-PSSnapin"),
+    ("rujb", r"Microsoft.PowerShell.Core\Resume-Job"),
+    ("rv", r"Microsoft.PowerShell.Utility\Remove-Variable"),
+    ("rvpa", r"Microsoft.PowerShell.Management\Resolve-Path"),
+    ("rwmi", r"Microsoft.PowerShell.Management\Remove-WmiObject"),
+    ("sajb", r"Microsoft.PowerShell.Core\Start-Job"),
+    ("sal", r"Microsoft.PowerShell.Utility\Set-Alias"),
+    ("saps", r"Microsoft.PowerShell.Management\Start-Process"),
+    ("sasv", r"Microsoft.PowerShell.Management\Start-Service"),
+    ("sbp", r"Microsoft.PowerShell.Utility\Set-PSBreakpoint"),
+    ("sc", r"Microsoft.PowerShell.Management\Set-Content"),
+    ("scb", r"Microsoft.PowerShell.Management\Set-Clipboard"),
+    ("select", r"Microsoft.PowerShell.Utility\Select-Object"),
+    ("set", r"Microsoft.PowerShell.Utility\Set-Variable"),
+    ("shcm", r"Microsoft.PowerShell.Utility\Show-Command"),
+    ("si", r"Microsoft.PowerShell.Management\Set-Item"),
+    ("sl", r"Microsoft.PowerShell.Management\Set-Location"),
+    ("sleep", r"Microsoft.PowerShell.Utility\Start-Sleep"),
+    ("sls", r"Microsoft.PowerShell.Utility\Select-String"),
+    ("sort", r"Microsoft.PowerShell.Utility\Sort-Object"),
+    ("sp", r"Microsoft.PowerShell.Management\Set-ItemProperty"),
+    ("spjb", r"Microsoft.PowerShell.Core\Stop-Job"),
+    ("spps", r"Microsoft.PowerShell.Management\Stop-Process"),
+    ("spsv", r"Microsoft.PowerShell.Management\Stop-Service"),
+    ("start", r"Microsoft.PowerShell.Management\Start-Process"),
+    ("stz", r"Microsoft.PowerShell.Management\Set-TimeZone"),
+    ("sujb", r"Microsoft.PowerShell.Core\Suspend-Job"),
+    ("sv", r"Microsoft.PowerShell.Utility\Set-Variable"),
+    ("swmi", r"Microsoft.PowerShell.Management\Set-WmiInstance"),
+    ("tee", r"Microsoft.PowerShell.Utility\Tee-Object"),
+    ("trcm", r"Microsoft.PowerShell.Utility\Trace-Command"),
+    ("type", r"Microsoft.PowerShell.Management\Get-Content"),
+    ("wget", r"Microsoft.PowerShell.Utility\Invoke-WebRequest"),
+    ("where", r"Microsoft.PowerShell.Core\Where-Object"),
+    ("wjb", r"Microsoft.PowerShell.Core\Wait-Job"),
+    ("write", r"Microsoft.PowerShell.Utility\Write-Output"),
+];
+
+/// Names of `BUILTIN_ALIAS_TARGETS`, the list reported as conflicts.
+const BUILTIN_ALIASES: [&str; BUILTIN_ALIAS_TARGETS.len()] = {
+    let mut names = [""; BUILTIN_ALIAS_TARGETS.len()];
+    let mut index = 0;
+    while index < names.len() {
+        names[index] = BUILTIN_ALIAS_TARGETS[index].0;
+        index += 1;
+    }
+    names
+};
+
+/// PowerShell names are case-insensitive.
+fn is_builtin_alias(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    BUILTIN_ALIASES.contains(&lower.as_str())
+}
+
+/// Call to the command that a function named `name` wraps. A bare `name` would resolve
+/// to the function itself, so the executable or cmdlet is looked up with `Get-Command`,
+/// falling back to the command a removed built-in alias stood for.
+/// e.g. `ls` → `& (@(Get-Command -Name 'ls' ...) + '...\Get-ChildItem')[0]`
+fn powershell_self_call(name: &str) -> String {
+    let lower = name.to_lowercase();
+    let target = BUILTIN_ALIAS_TARGETS
+        .iter()
+        .find(|(alias, _)| *alias == lower)
+        .map(|(_, target)| *target);
+    let lookup = format!(
+        "Get-Command -Name '{}' -CommandType Application,Cmdlet -TotalCount 1",
+        powershell_single_quote(name)
+    );
+    match target {
+        // `?` and `*` are wildcards for Get-Command
+        Some(target) if name.contains(['?', '*', '[', ']']) => format!("& '{}'", target),
+        Some(target) => format!("& (@({} -ErrorAction Ignore) + '{}')[0]", lookup, target),
+        None => format!("& ({})", lookup),
+    }
+}
+
+/// A single command name with no arguments or special characters (e.g. `Get-ChildItem`).
+fn is_bare_command(command: &str) -> bool {
+    !command.is_empty()
+        && command
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '\\' | '/' | ':'))
+}
+
+/// Body of the function wrapper, forwarding the caller's arguments with `@args`.
+/// Commands that would not survive being inlined into `{ ... }` are embedded as a
+/// single-quoted string, parsed into a script block and called with the same `@args`,
+/// so every argument keeps its own quoting.
+fn powershell_body(command: &str) -> String {
+    let forwarded = if command.contains("$args") || command.contains("@args") {
+        command.to_string()
+    } else {
+        forward_args(command)
+    };
+    if is_inlinable(command) {
+        forwarded
+    } else {
+        format!(
+            "& ([scriptblock]::Create('{}')) @args",
+            powershell_single_quote(&forwarded)
+        )
+    }
+}
+
+/// Append `@args` to the first command: `ls | Where-Object { ... }` passes the
+/// arguments to `ls`, not to the filter, and `echo x # note` keeps them before the comment.
+fn forward_args(command: &str) -> String {
+    let (head, tail) = command.split_at(first_command_end(command));
+    let trimmed = head.trim_end();
+    format!("{} @args{}{}", trimmed, &head[trimmed.len()..], tail)
+}
+
+/// Byte offset where the first command ends: a top-level `|`, `;`, `&&`, `||`,
+/// line break or comment, skipping strings, here-strings and script blocks.
+fn first_command_end(command: &str) -> usize {
+    let mut depth = 0usize;
+    let mut chars = command.char_indices().peekable();
+    let mut previous = ' ';
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '`' => {
+                chars.next();
+            }
+            '\'' | '"' => {
+                // '' and "" are escaped quotes, backtick escapes inside double quotes
+                while let Some((_, next)) = chars.next() {
+                    if c == '"' && next == '`' {
+                        chars.next();
+                    } else if next == c && chars.next_if(|&(_, after)| after == c).is_none() {
+                        break;
+                    }
+                }
+            }
+            '@' if matches!(chars.peek(), Some((_, '\'' | '"'))) => {
+                // A here-string closes with its quote and @ at the start of a line
+                let quote = chars.next().map_or('\'', |(_, quote)| quote);
+                let close = format!("\n{}@", quote);
+                let Some(offset) = command[index..].find(&close) else {
+                    return command.len();
+                };
+                let end = index + offset + close.len();
+                while chars.next_if(|&(next, _)| next < end).is_some() {}
+            }
+            '{' | '(' => depth += 1,
+            '}' | ')' => depth = depth.saturating_sub(1),
+            '|' | ';' | '\n' | '\r' if depth == 0 => return index,
+            '&' if depth == 0 && matches!(chars.peek(), Some((_, '&'))) => return index,
+            '#' if depth == 0 && previous.is_whitespace() => return index,
+            _ => {}
+        }
+        previous = c;
+    }
+    command.len()
+}
+
+/// Escape text for a PowerShell single-quoted string: every quote character is doubled.
+/// PowerShell also treats the typographic quotes as single quotes.
+fn powershell_single_quote(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            escaped.push(c);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Whether `command` can be placed inside a script block on a single line:
+/// balanced quotes, braces and parentheses, no comments, no here-strings.
+fn is_inlinable(command: &str) -> bool {
+    if command.contains('\n') || command.contains('\r') {
+        return false;
+    }
+
+    let mut depth: Vec<char> = Vec::new();
+    let mut chars = command.chars().peekable();
+    let mut previous = ' ';
+
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => {
+                // Backtick escapes the next character
+                chars.next();
+            }
+            '\'' => {
+                // Single-quoted string: '' is an escaped quote
+                loop {
+                    match chars.next() {
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                        }
+                        Some('\'') => break,
+                        Some(_) => {}
+                        None => return false,
+                    }
+                }
+            }
+            '"' => {
+                // Double-quoted string: backtick escapes, "" is an escaped quote
+                loop {
+                    match chars.next() {
+                        Some('`') => {
+                            chars.next();
+                        }
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                        }
+                        Some('"') => break,
+                        Some(_) => {}
+                        None => return false,
+                    }
+                }
+            }
+            // Here-strings (@' and @") span several lines
+            '@' if matches!(chars.peek(), Some('\'') | Some('"')) => return false,
+            // A comment would swallow the closing brace
+            '#' if previous.is_whitespace() || previous == '<' => return false,
+            '{' | '(' => depth.push(c),
+            '}' if depth.pop() != Some('{') => return false,
+            ')' if depth.pop() != Some('(') => return false,
+            _ => {}
+        }
+        previous = c;
+    }
+    depth.is_empty()
+}
+
+/// `:: note`, or `rem`/`REM note` in any case: cmd.exe keywords ignore case.
+fn is_cmd_comment(line: &str) -> bool {
+    let first_word = line.split_whitespace().next().unwrap_or_default();
+    line.starts_with("::") || first_word.eq_ignore_ascii_case("rem")
+}
+
+impl Shell for Cmd {
+    fn name(&self) -> &'static str {
+        "Cmd"
+    }
+
+    fn shell_type(&self) -> ShellType {
+        ShellType::Cmd
+    }
+
+    fn alias_syntax(&self, name: &str, command: &str) -> String {
+        // doskey macro file line: name=command $*
+        // $* forwards every argument typed after the macro name
+        format!("{}={} $*", name, doskey_escape(command))
+    }
+
+    fn function_syntax(&self, name: &str, segments: &[Segment]) -> String {
+        // doskey only knows $1..$9 and $* (all arguments). A higher {N} is kept as-is,
+        // so the syntax check refuses the macro instead of it running another command
+        let body: String = segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => doskey_escape(text),
+                Segment::Arg(n) if *n <= 9 => format!("${}", n),
+                Segment::Arg(n) => format!("{{{}}}", n),
+                Segment::AllArgs => String::from("$*"),
+            })
+            .collect();
+        format!("{}={}", name, body)
+    }
+
+    fn function_block(&self, name: &str, body: &str) -> String {
+        // A macro is a single line: run the body's lines one after another with $T
+        let commands: Vec<String> = body
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !is_cmd_comment(line))
+            .map(doskey_escape)
+            .collect();
+        format!("{}={}", name, commands.join(" $T "))
+    }
+
+    fn defined_name(&self, line: &str) -> Option<String> {
+        // Every macro is a single `NAME=...` line
+        line.contains('=')
+            .then(|| name_before(line, &['=']))
+            .flatten()
+    }
+
+    fn nesting(&self, _line: &str) -> isize {
+        // Braces are plain text in a macro, and no macro spans several lines
+        0
+    }
+
+    fn script_extension(&self) -> &'static str {
+        "doskey"
+    }
+
+    fn source_line(&self, _path: &Path) -> Option<String> {
+        // A macro file cannot load another one, and it is already owned by akash
+        None
+    }
+
+    fn hook_line(&self) -> Option<String> {
+        // doskey only reads macro files
+        None
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        // Keywords such as `if` and `for` are internal commands too
+        &[]
+    }
+
+    fn builtins(&self) -> &'static [&'static str] {
+        CMD_BUILTINS
+    }
+
+    fn config_path(&self) -> Result<PathBuf> {
+        // cmd.exe has no rc file: akash owns a macro file loaded via doskey
+        let home =
+            dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Cannot determine home directory"))?;
+        Ok(home.join(".akash").join("macros.doskey"))
+    }
+
+    fn reload_instructions(&self) -> String {
+        // AutoRun is shared with Conda, Clink and others: never overwrite it
+        String::from(
+            "Run: doskey /macrofile=\"%USERPROFILE%\\.akash\\macros.doskey\"\n\
+             To load it in every new cmd.exe, add that command to the AutoRun registry value.\n\
+             Check first whether other tools already use it:\n  \
+             reg query \"HKCU\\Software\\Microsoft\\Command Processor\" /v AutoRun\n\
+             If there is no value, create it:\n  \
+             reg add \"HKCU\\Software\\Microsoft\\Command Processor\" /v AutoRun /t REG_EXPAND_SZ \
+             /d \"doskey /macrofile=\\\"%USERPROFILE%\\.akash\\macros.doskey\\\"\"\n\
+             Otherwise keep the current value and chain ours after it with &:\n  \
+             <current value> & doskey /macrofile=\"%USERPROFILE%\\.akash\\macros.doskey\"",
+        )
+    }
+
+    /// doskey treats lines starting with ";=" as harmless no-op macros,
+    /// which is the conventional way to write comments in a macro file.
+    fn comment_prefix(&self) -> &'static str {
+        ";="
+    }
+}
+
+/// Translate cmd.exe metacharacters into doskey macro codes.
+/// `$` -> `$$`, `|` -> `$B`, `>` -> `$G`, `<` -> `$L`, `&`/`&&` -> `$T`
+fn doskey_escape(command: &str) -> String {
+    let mut escaped = String::with_capacity(command.len());
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '$' => escaped.push_str("$$"),
+            '|' => escaped.push_str("$B"),
+            '<' => escaped.push_str("$L"),
+            '>' => {
+                escaped.push_str("$G");
+                // Keep handle duplication (2>&1) intact: that & is not a separator
+                if chars.peek() == Some(&'&') {
+                    chars.next();
+                    escaped.push('&');
+                }
+            }
+            '&' => {
+                // doskey has no conditional execution, && becomes a plain separator
+                if chars.peek() == Some(&'&') {
+                    chars.next();
+                }
+                escaped.push_str("$T");
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasStore;
+
+    // --- PowerShell::alias_syntax (golden outputs) ---
+
+    #[test]
+    fn given_bare_command_when_generating_powershell_alias_then_uses_set_alias() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.alias_syntax("g", "git");
+
+        // Then
+        assert_eq!(line, "Set-Alias -Name g -Value git -Scope Global");
+    }
+
+    #[test]
+    fn given_command_with_arguments_when_generating_powershell_alias_then_forwards_args() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.alias_syntax("gs", "git status");
+
+        // Then
+        assert_eq!(line, "function global:gs { git status @args }");
+    }
+
+    #[test]
+    fn given_builtin_alias_name_when_generating_powershell_function_then_removes_alias_first() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.alias_syntax("ls", "Get-ChildItem -Force");
+
+        // Then
+        assert_eq!(
+            line,
+            "Remove-Item -Path Alias:ls -Force -ErrorAction SilentlyContinue\n\
+             function global:ls { Get-ChildItem -Force @args }"
+        );
+    }
+
+    #[test]
+    fn given_builtin_alias_name_when_generating_set_alias_then_forces_it() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.alias_syntax("CD", "Push-Location");
+
+        // Then
+        assert_eq!(
+            line,
+            "Remove-Item -Path Alias:CD -Force -ErrorAction SilentlyContinue\n\
+             Set-Alias -Name CD -Value Push-Location -Scope Global -Force"
+        );
+    }
+
+    #[test]
+    fn given_reported_builtin_when_generating_powershell_alias_then_it_is_removed_first() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let missing: Vec<&str> = shell
+            .builtins()
+            .iter()
+            .copied()
+            .filter(|name| {
+                !shell
+                    .alias_syntax(name, "Get-Date")
+                    .starts_with("Remove-Item")
+            })
+            .collect();
+
+        // Then
+        assert!(missing.is_empty(), "not removed: {:?}", missing);
+        assert!(shell.builtins().contains(&"curl"));
+        assert!(shell.builtins().contains(&"%"));
+    }
+
+    #[test]
+    fn given_wrapper_named_after_its_command_when_generating_powershell_alias_then_no_recursion() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let builtin = shell.alias_syntax("ls", "ls -la");
+        let external = shell.alias_syntax("grep", "grep --color | more");
+
+        // Then
+        assert_eq!(
+            builtin,
+            "Remove-Item -Path Alias:ls -Force -ErrorAction SilentlyContinue\n\
+             function global:ls { & (@(Get-Command -Name 'ls' -CommandType Application,Cmdlet -TotalCount 1 -ErrorAction Ignore) + 'Microsoft.PowerShell.Management\\Get-ChildItem')[0] -la @args }"
+        );
+        assert_eq!(
+            external,
+            "function global:grep { & (Get-Command -Name 'grep' -CommandType Application,Cmdlet -TotalCount 1) --color @args | more }"
+        );
+    }
+
+    #[test]
+    fn given_balanced_script_block_when_generating_powershell_alias_then_it_is_inlined() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.alias_syntax("big", "ls | Where-Object { $_.Length -gt 1MB }");
+
+        // Then
+        assert_eq!(
+            line,
+            "function global:big { ls @args | Where-Object { $_.Length -gt 1MB } }"
+        );
+    }
+
+    #[test]
+    fn given_several_commands_when_generating_powershell_alias_then_args_go_to_the_first() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let sequence = shell.alias_syntax("up", "git pull; git log -1 '|;'");
+        let chained = shell.alias_syntax("bt", "cargo build && cargo test");
+
+        // Then
+        assert_eq!(
+            sequence,
+            "function global:up { git pull @args; git log -1 '|;' }"
+        );
+        assert_eq!(
+            chained,
+            "function global:bt { cargo build @args && cargo test }"
+        );
+    }
+
+    #[test]
+    fn given_explicit_args_when_generating_powershell_alias_then_they_are_not_forwarded_twice() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.alias_syntax("mk", "New-Item -ItemType Directory @args");
+
+        // Then
+        assert_eq!(
+            line,
+            "function global:mk { New-Item -ItemType Directory @args }"
+        );
+    }
+
+    #[test]
+    fn given_unbalanced_brace_when_generating_powershell_alias_then_uses_quoted_fallback() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.alias_syntax("br", "echo }");
+
+        // Then
+        assert_eq!(
+            line,
+            "function global:br { & ([scriptblock]::Create('echo } @args')) @args }"
+        );
+    }
+
+    #[test]
+    fn given_quotes_and_comment_when_generating_powershell_alias_then_fallback_is_escaped() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.alias_syntax("say", "echo 'it''s' # done");
+
+        // Then
+        assert_eq!(
+            line,
+            "function global:say { & ([scriptblock]::Create('echo ''it''''s'' @args # done')) @args }"
+        );
+    }
+
+    #[test]
+    fn given_unterminated_string_when_generating_powershell_alias_then_uses_quoted_fallback() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.alias_syntax("q", "echo \"$env:HOME");
+
+        // Then
+        assert_eq!(
+            line,
+            "function global:q { & ([scriptblock]::Create('echo \"$env:HOME @args')) @args }"
+        );
+    }
+
+    // --- PowerShell::function_syntax ---
+
+    #[test]
+    fn given_placeholders_when_generating_powershell_entry_then_uses_param_block() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.entry_syntax("gco", "git checkout {1}");
+
+        // Then
+        assert_eq!(line, "function global:gco { param($p1) git checkout $p1 }");
+    }
+
+    #[test]
+    fn given_all_args_placeholder_when_generating_powershell_entry_then_splats_args() {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.entry_syntax("gcmsg", "git commit -m {1} {@}");
+
+        // Then
+        assert_eq!(
+            line,
+            "function global:gcmsg { param($p1) git commit -m $p1 $p1 @args }"
+        );
+    }
+
+    #[test]
+    fn given_placeholder_named_after_its_command_when_generating_powershell_entry_then_no_recursion()
+     {
+        // Given
+        let shell = PowerShell;
+
+        // When
+        let line = shell.entry_syntax("cd", "cd {1}");
+
+        // Then
+        assert_eq!(
+            line,
+            "Remove-Item -Path Alias:cd -Force -ErrorAction SilentlyContinue\n\
+             function global:cd { param($p1) & (@(Get-Command -Name 'cd' -CommandType Application,Cmdlet -TotalCount 1 -ErrorAction Ignore) + 'Microsoft.PowerShell.Management\\Set-Location')[0] $p1 }"
+        );
+    }
+
+    // --- source_line ---
+
+    #[test]
+    fn given_path_when_generating_powershell_source_line_then_dot_sources() {
+        // Given
+        let path = Path::new(r"C:\Users\o'neil\.akash\generated\aliases.ps1");
+
+        // When
+        let line = PowerShell.source_line(path).unwrap();
+
+        // Then
+        assert_eq!(
+            line,
+            r"if (Test-Path 'C:\Users\o''neil\.akash\generated\aliases.ps1') { . 'C:\Users\o''neil\.akash\generated\aliases.ps1' }"
+        );
+    }
+
+    #[test]
+    fn given_cmd_when_generating_source_line_then_none() {
+        // Given / When / Then
+        assert_eq!(Cmd.source_line(Path::new("macros.doskey")), None);
+        assert_eq!(Cmd.hook_line(), None);
+    }
+
+    // --- Cmd::function_syntax ---
+
+    #[test]
+    fn given_placeholders_when_generating_doskey_entry_then_uses_dollar_digits() {
+        // Given
+        let shell = Cmd;
+
+        // When
+        let line = shell.entry_syntax("gco", "git checkout {1} | more");
+
+        // Then
+        assert_eq!(line, "gco=git checkout $1 $B more");
+    }
+
+    #[test]
+    fn given_placeholder_above_nine_when_generating_doskey_entry_then_it_is_not_rewritten() {
+        // Given
+        let shell = Cmd;
+
+        // When
+        let line = shell.entry_syntax("many", "echo {9} {10} {@}");
+
+        // Then
+        assert_eq!(line, "many=echo $9 {10} $*");
+    }
+
+    // --- Cmd::alias_syntax ---
+
+    #[test]
+    fn given_simple_command_when_generating_doskey_macro_then_forwards_arguments() {
+        // Given
+        let shell = Cmd;
+
+        // When
+        let line = shell.alias_syntax("gs", "git status");
+
+        // Then
+        assert_eq!(line, "gs=git status $*");
+    }
+
+    #[test]
+    fn given_pipes_and_redirects_when_generating_doskey_macro_then_they_are_translated() {
+        // Given
+        let shell = Cmd;
+
+        // When
+        let line = shell.alias_syntax("lsf", "dir /b | findstr foo > out.txt");
+
+        // Then
+        assert_eq!(line, "lsf=dir /b $B findstr foo $G out.txt $*");
+    }
+
+    #[test]
+    fn given_command_separators_when_generating_doskey_macro_then_they_become_t() {
+        // Given
+        let shell = Cmd;
+
+        // When
+        let line = shell.alias_syntax("up", "cd .. && dir & cls");
+
+        // Then
+        assert_eq!(line, "up=cd .. $T dir $T cls $*");
+    }
+
+    #[test]
+    fn given_dollar_and_handle_redirect_when_generating_doskey_macro_then_they_are_preserved() {
+        // Given
+        let shell = Cmd;
+
+        // When
+        let line = shell.alias_syntax("b", "build $env 2>&1 < in.txt");
+
+        // Then
+        assert_eq!(line, "b=build $$env 2$G&1 $L in.txt $*");
+    }
+
+    // --- Cmd::function_block ---
+
+    #[test]
+    fn given_multiline_body_when_generating_doskey_function_then_lines_are_joined() {
+        // Given
+        let shell = Cmd;
+
+        // When
+        let line = shell.function_block("up", "cd ..\r\n:: list it\r\ndir /b\r\n");
+
+        // Then
+        assert_eq!(line, "up=cd .. $T dir /b");
+    }
+
+    #[test]
+    fn given_rem_comments_in_any_case_when_generating_doskey_function_then_they_are_dropped() {
+        // Given
+        let shell = Cmd;
+
+        // When
+        let line = shell.function_block(
+            "up",
+            "REM go up
+cd ..
+rem
+Rem	then list
+dir /b",
+        );
+
+        // Then
+        assert_eq!(line, "up=cd .. $T dir /b");
+    }
+
+    // --- Cmd::reload_instructions ---
+
+    #[test]
+    fn given_cmd_when_getting_reload_instructions_then_autorun_is_never_forced() {
+        // Given / When
+        let text = Cmd.reload_instructions();
+
+        // Then
+        assert!(text.contains("reg query"));
+        assert!(!text.contains(" /f"));
+    }
+
+    // --- Cmd::generate_alias_block ---
+
+    #[test]
+    fn given_aliases_when_generating_doskey_block_then_markers_are_doskey_comments() {
+        // Given
+        let shell = Cmd;
+        let mut store = AliasStore::new_store();
+        store.add_alias("gs".into(), "git status".into());
+
+        // When
+        let block = shell.generate_alias_block(&store);
+
+        // Then
+        assert_eq!(
+            block,
+            ";= BEGIN akash aliases\ngs=git status $*\n;= END akash aliases"
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use crate::fsutil::write_atomic;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Entries of one managed block: definition text by alias name
+pub type Entries = BTreeMap<String, String>;
+
+/// What `apply` last wrote into each shell config: ~/.akash/state.json
+/// Comparing it with the block on disk tells hand edits apart from
+/// entries that are merely behind the store.
+pub struct AppliedState {
+    path: PathBuf,
+}
+
+impl AppliedState {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Default location: ~/.akash/state.json
+    pub fn open_default() -> Result<Self> {
+        let home =
+            dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Cannot determine home directory"))?;
+        Ok(Self::new(home.join(".akash").join("state.json")))
+    }
+
+    fn load(&self) -> Result<BTreeMap<PathBuf, Entries>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let content = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", self.path.display()))
+    }
+
+    /// Entries last applied to `config_path`, or None if akash has no record of it.
+    pub fn entries_for(&self, config_path: &Path) -> Result<Option<Entries>> {
+        Ok(self.load()?.remove(config_path))
+    }
+
+    /// Remember the entries just written to `config_path`.
+    pub fn record(&self, config_path: &Path, entries: Entries) -> Result<()> {
+        let mut state = self.load()?;
+        state.insert(config_path.to_path_buf(), entries);
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        let content = serde_json::to_string_pretty(&state).context("Failed to serialize state")?;
+        write_atomic(&self.path, content.as_bytes())
+    }
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_recorded_entries_when_reading_then_returns_them_per_config() {
+        // Given
+        let dir = std::env::temp_dir().join(format!("akash-test-state-{}", std::process::id()));
+        let state = AppliedState::new(dir.join("state.json"));
+        let entries: Entries =
+            BTreeMap::from([(String::from("gs"), String::from("alias gs='git status'"))]);
+
+        // When
+        state
+            .record(Path::new("/home/u/.bashrc"), entries.clone())
+            .unwrap();
+
+        // Then
+        assert_eq!(
+            state.entries_for(Path::new("/home/u/.bashrc")).unwrap(),
+            Some(entries)
+        );
+        assert_eq!(
+            state.entries_for(Path::new("/home/u/.zshrc")).unwrap(),
+            None
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
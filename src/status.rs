@@ -0,0 +1,151 @@
+use crate::state::Entries;
+
+/// How one entry of a shell config differs from the store.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Drift {
+    /// In the store, not in the config
+    Missing,
+    /// The config still has the previously applied definition
+    Stale,
+    /// Removed from the store, still in the config
+    Removed,
+    /// Definition changed in the config since the last apply
+    Modified,
+    /// Added to the block by hand
+    Added,
+}
+
+impl Drift {
+    /// Short label for the status table
+    pub fn label(&self) -> &'static str {
+        match self {
+            Drift::Missing => "missing",
+            Drift::Stale | Drift::Removed => "stale",
+            Drift::Modified | Drift::Added => "modified",
+        }
+    }
+
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Drift::Missing => "in the store but not in the config",
+            Drift::Stale => "changed in the store since the last apply",
+            Drift::Removed => "removed from the store, still in the config",
+            Drift::Modified => "edited by hand in the config",
+            Drift::Added => "added by hand to the managed block",
+        }
+    }
+}
+
+/// Compare the entries the store generates with the ones found in the config.
+/// `applied` is what the last apply wrote: an entry that differs from it was
+/// edited by hand. Without a record, differences are assumed to be stale.
+pub fn compare(
+    expected: &Entries,
+    actual: &Entries,
+    applied: Option<&Entries>,
+) -> Vec<(String, Drift)> {
+    let untouched = |name: &str, text: &str| match applied {
+        Some(applied) => applied.get(name).map(String::as_str) == Some(text),
+        None => true,
+    };
+
+    let mut names: Vec<&String> = expected.keys().chain(actual.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let drift = match (expected.get(name), actual.get(name)) {
+                (Some(_), None) => Drift::Missing,
+                (None, Some(text)) if untouched(name, text) => Drift::Removed,
+                (None, Some(_)) => Drift::Added,
+                (Some(wanted), Some(text)) if wanted == text => return None,
+                (Some(_), Some(text)) if untouched(name, text) => Drift::Stale,
+                (Some(_), Some(_)) => Drift::Modified,
+                (None, None) => return None,
+            };
+            Some((name.clone(), drift))
+        })
+        .collect()
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(pairs: &[(&str, &str)]) -> Entries {
+        pairs
+            .iter()
+            .map(|(name, text)| (name.to_string(), text.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn given_identical_entries_when_comparing_then_no_drift() {
+        // Given
+        let expected = entries(&[("gs", "alias gs='git status'")]);
+
+        // When
+        let drift = compare(&expected, &expected.clone(), None);
+
+        // Then
+        assert!(drift.is_empty());
+    }
+
+    #[test]
+    fn given_applied_record_when_comparing_then_tells_stale_from_hand_edits() {
+        // Given
+        let applied = entries(&[
+            ("gs", "alias gs='git status'"),
+            ("ll", "alias ll='ls -l'"),
+            ("old", "alias old='x'"),
+        ]);
+        let expected = entries(&[
+            ("gs", "alias gs='git status -s'"),
+            ("ll", "alias ll='ls -l'"),
+            ("new", "alias new='y'"),
+        ]);
+        let actual = entries(&[
+            ("gs", "alias gs='git status'"),
+            ("ll", "alias ll='ls -la'"),
+            ("old", "alias old='x'"),
+            ("mine", "alias mine='z'"),
+        ]);
+
+        // When
+        let drift = compare(&expected, &actual, Some(&applied));
+
+        // Then
+        assert_eq!(
+            drift,
+            vec![
+                (String::from("gs"), Drift::Stale),
+                (String::from("ll"), Drift::Modified),
+                (String::from("mine"), Drift::Added),
+                (String::from("new"), Drift::Missing),
+                (String::from("old"), Drift::Removed),
+            ]
+        );
+    }
+
+    #[test]
+    fn given_no_applied_record_when_comparing_then_differences_are_stale() {
+        // Given
+        let expected = entries(&[("gs", "alias gs='git status -s'")]);
+        let actual = entries(&[("gs", "alias gs='git status'"), ("x", "alias x='y'")]);
+
+        // When
+        let drift = compare(&expected, &actual, None);
+
+        // Then
+        assert_eq!(
+            drift,
+            vec![
+                (String::from("gs"), Drift::Stale),
+                (String::from("x"), Drift::Removed),
+            ]
+        );
+    }
+}
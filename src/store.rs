@@ -1,21 +1,163 @@
+mod migrate;
+
+use crate::fsutil::write_atomic;
+use crate::timestamp::now_rfc3339;
 use anyhow::{Context, Result};
+use migrate::CURRENT_VERSION;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 use tracing::{debug, info};
 
-/// JSON schema: {aliases: {alias_name: command, ...}}
+/// JSON schema: {version, aliases: {alias_name: {command, description, tags, ...}, ...}, functions: {function_name: {body, variants}, ...}}
 /// BTreeMap is used to maintain sorted order of aliases for consistent display and testing.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AliasStore {
-    pub aliases: BTreeMap<String, String>,
+    /// Schema version, see `migrate` for the history
+    pub version: u64,
+
+    pub aliases: BTreeMap<String, AliasEntry>,
+
+    /// Multi-line shell functions. Omitted from the JSON when empty,
+    /// so stores without functions keep the original format.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub functions: BTreeMap<String, FunctionDef>,
+}
+
+/// One alias and what we know about it.
+/// Only `command` is required; metadata fields are omitted from the JSON when unset.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AliasEntry {
+    /// Command the alias expands to
+    pub command: String,
+
+    /// Why the alias exists
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Free-form labels used to group and filter aliases
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// RFC 3339 UTC timestamp of creation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+
+    /// RFC 3339 UTC timestamp of the last change to the command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+
+    /// User who created the alias
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+impl AliasEntry {
+    /// New entry stamped with the current time and user.
+    pub fn new(command: String) -> Self {
+        let now = now_rfc3339();
+        Self {
+            command,
+            created_at: Some(now.clone()),
+            updated_at: Some(now),
+            author: current_user(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the entry has every tag in `tags` (case-insensitive).
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter()
+            .all(|wanted| self.tags.iter().any(|tag| tag.eq_ignore_ascii_case(wanted)))
+    }
+}
+
+/// Criteria for `akash list`. An empty filter matches everything.
+#[derive(Debug, Default)]
+pub struct AliasFilter {
+    /// Entry must carry all of these tags
+    pub tags: Vec<String>,
+    /// Entry author must match (case-insensitive)
+    pub author: Option<String>,
+    /// Case-insensitive substring of the name, command or description
+    pub search: Option<String>,
+}
+
+impl AliasFilter {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.author.is_none() && self.search.is_none()
+    }
+
+    pub fn matches(&self, name: &str, entry: &AliasEntry) -> bool {
+        if !entry.has_tags(&self.tags) {
+            return false;
+        }
+
+        if let Some(author) = &self.author {
+            let same_author = entry
+                .author
+                .as_deref()
+                .is_some_and(|a| a.eq_ignore_ascii_case(author));
+            if !same_author {
+                return false;
+            }
+        }
+
+        if let Some(search) = &self.search {
+            let needle = search.to_lowercase();
+            let found = name.to_lowercase().contains(&needle)
+                || entry.command.to_lowercase().contains(&needle)
+                || entry
+                    .description
+                    .as_deref()
+                    .is_some_and(|d| d.to_lowercase().contains(&needle));
+            if !found {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Login name of the current user, if the environment tells us.
+fn current_user() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|user| !user.is_empty())
+}
+
+/// A shell function: a script body, plus optional per-shell bodies
+/// for shells where the syntax differs (e.g. PowerShell vs Bash).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FunctionDef {
+    /// Default body, used by every shell without a variant
+    #[serde(default)]
+    pub body: String,
+
+    /// Per-shell bodies keyed by shell (e.g. "powershell", "fish")
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variants: BTreeMap<String, String>,
+}
+
+impl FunctionDef {
+    /// Body to use for a given shell key, if any.
+    pub fn body_for(&self, shell_key: &str) -> Option<&str> {
+        match self.variants.get(shell_key) {
+            Some(body) => Some(body.as_str()),
+            None if !self.body.is_empty() => Some(self.body.as_str()),
+            None => None,
+        }
+    }
 }
 
 impl AliasStore {
     /// Create a new empty store.
     pub fn new_store() -> Self {
         Self {
+            version: CURRENT_VERSION,
             aliases: BTreeMap::new(),
+            functions: BTreeMap::new(),
         }
     }
 
@@ -50,13 +192,59 @@ impl AliasStore {
         let content = std::fs::read_to_string(&path)
             .with_context(|| format!("Failed to read alias store from {}", path.display()))?;
 
-        let store: Self = serde_json::from_str(&content)
+        let (store, original_version) = Self::from_json(&content)
             .with_context(|| format!("Failed to parse alias store JSON from {}", path.display()))?;
 
-        debug!("Loaded alias store with {} aliases", store.aliases.len());
+        // Keep the pre-migration file around, then persist the upgraded store
+        if original_version < CURRENT_VERSION {
+            let backup = Self::migration_backup_path(&path, original_version);
+            std::fs::copy(&path, &backup).with_context(|| {
+                format!("Failed to back up alias store to {}", backup.display())
+            })?;
+            info!(
+                "Migrated alias store from v{} to v{}, backup at {}",
+                original_version,
+                CURRENT_VERSION,
+                backup.display()
+            );
+            store.store_save(Some(&path))?;
+        }
+
+        debug!(
+            "Loaded alias store with {} aliases and {} functions",
+            store.aliases.len(),
+            store.functions.len()
+        );
         Ok(store)
     }
 
+    /// Parse a store from JSON, migrating it from any older schema.
+    /// Returns the store and the schema version the JSON was written with.
+    pub fn from_json(content: &str) -> Result<(Self, u64)> {
+        let mut value: serde_json::Value = serde_json::from_str(content)?;
+        let original_version = migrate::migrate(&mut value)?;
+        let store: Self = serde_json::from_value(value)?;
+        Ok((store, original_version))
+    }
+
+    /// Where the pre-migration copy goes: ~/.akash/aliases.json.v1.bak, or
+    /// aliases.json.v1.2.bak and so on when an earlier migration already left one.
+    fn migration_backup_path(path: &std::path::Path, version: u64) -> PathBuf {
+        let file_name = path.file_name().unwrap_or_default();
+        (1..)
+            .map(|copy| {
+                let mut name = file_name.to_os_string();
+                if copy == 1 {
+                    name.push(format!(".v{}.bak", version));
+                } else {
+                    name.push(format!(".v{}.{}.bak", version, copy));
+                }
+                path.with_file_name(name)
+            })
+            .find(|backup| !backup.exists())
+            .unwrap_or_else(|| path.with_extension("bak"))
+    }
+
     /// Save the store to disk, creating the directory if it doesn't exist.
     pub fn store_save(&self, custom_path: Option<&PathBuf>) -> Result<()> {
         let path = Self::store_path(custom_path)?;
@@ -73,7 +261,7 @@ impl AliasStore {
         let content = serde_json::to_string_pretty(self)
             .context("Failed to serialize alias store to JSON")?;
 
-        std::fs::write(&path, content)
+        write_atomic(&path, content.as_bytes())
             .with_context(|| format!("Failed to write alias store to {}", path.display()))?;
 
         // Debug displays: {"level":"DEBUG", "message":"Saved alias store with N aliases", "path":"/home/user/.akash/aliases.json"}
@@ -81,23 +269,63 @@ impl AliasStore {
         Ok(())
     }
 
-    /// Add an alias to the store and save it.
+    /// Add an alias to the store, or update the command of an existing one.
+    /// Updating keeps the alias metadata and refreshes `updated_at`.
     pub fn add_alias(&mut self, alias_name: String, command: String) -> bool {
-        let is_new = !self.aliases.contains_key(&alias_name);
+        // A name is either an alias or a function, never both
+        if self.functions.remove(&alias_name).is_some() {
+            debug!("Replacing function '{}' with an alias", alias_name);
+        }
 
-        if is_new {
-            debug!("Adding new alias: {} -> {}", alias_name, command);
-        } else {
-            debug!("Updating existing alias: {} -> {}", alias_name, command);
+        match self.aliases.get_mut(&alias_name) {
+            Some(entry) => {
+                debug!("Updating existing alias: {} -> {}", alias_name, command);
+                if entry.command != command {
+                    entry.command = command;
+                    entry.updated_at = Some(now_rfc3339());
+                }
+                false
+            }
+            None => {
+                debug!("Adding new alias: {} -> {}", alias_name, command);
+                self.aliases.insert(alias_name, AliasEntry::new(command));
+                true
+            }
         }
+    }
+
+    /// Mutable access to an alias entry, to edit its metadata.
+    pub fn alias_mut(&mut self, alias_name: &str) -> Option<&mut AliasEntry> {
+        self.aliases.get_mut(alias_name)
+    }
+
+    /// Add or update a function. With `shell_key`, only that shell's variant is set.
+    /// Returns true if the function is new.
+    pub fn add_function(&mut self, name: String, body: String, shell_key: Option<&str>) -> bool {
+        let is_new = !self.functions.contains_key(&name);
 
-        self.aliases.insert(alias_name, command);
+        if self.aliases.remove(&name).is_some() {
+            debug!("Replacing alias '{}' with a function", name);
+        }
+
+        let function = self.functions.entry(name.clone()).or_default();
+        match shell_key {
+            Some(key) => {
+                debug!("Setting {} variant of function: {}", key, name);
+                function.variants.insert(key.to_string(), body);
+            }
+            None => {
+                debug!("Setting body of function: {}", name);
+                function.body = body;
+            }
+        }
         is_new
     }
 
-    /// Remove an alias. Returns true if found and removed.
+    /// Remove an alias or function. Returns true if found and removed.
     pub fn remove_alias(&mut self, alias_name: &str) -> bool {
-        let removed = self.aliases.remove(alias_name).is_some();
+        let removed = self.aliases.remove(alias_name).is_some()
+            || self.functions.remove(alias_name).is_some();
         if removed {
             info!("Removed alias: {}", alias_name);
         } else {
@@ -107,14 +335,21 @@ impl AliasStore {
     }
 
     /// Reference to all aliases.
-    pub fn list_aliases(&self) -> &BTreeMap<String, String> {
+    pub fn list_aliases(&self) -> &BTreeMap<String, AliasEntry> {
         info!("Listing {} aliases", self.aliases.len());
         &self.aliases
     }
 
-    /// Check if an alias exists.
+    /// Reference to all functions.
+    pub fn list_functions(&self) -> &BTreeMap<String, FunctionDef> {
+        info!("Listing {} functions", self.functions.len());
+        &self.functions
+    }
+
+    /// Check if an alias or function exists.
     pub fn has_key(&self, alias_name: &str) -> bool {
-        let exists = self.aliases.contains_key(alias_name);
+        let exists =
+            self.aliases.contains_key(alias_name) || self.functions.contains_key(alias_name);
         info!(
             "Checking alias '{}': {}",
             alias_name,
@@ -150,6 +385,13 @@ impl AliasStore {
         debug!("Alias name '{}' is valid", alias_name);
         Ok(())
     }
+
+    /// Whether every POSIX shell accepts `name` for a function. dash (sh) only takes letters,
+    /// digits and underscores, not starting with a digit: `my-x() {` is a syntax error there.
+    pub fn is_portable_function_name(name: &str) -> bool {
+        !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
 }
 
 /// UNIT TESTS
@@ -157,6 +399,23 @@ impl AliasStore {
 mod tests {
     use super::*;
 
+    // --- is_portable_function_name ---
+
+    #[test]
+    fn given_names_when_checking_portable_function_name_then_rejects_hyphens_and_leading_digits() {
+        // Given
+        let names = ["gco", "git_co", "x2", "my-x", "2x", "café"];
+
+        // When
+        let portable: Vec<bool> = names
+            .iter()
+            .map(|name| AliasStore::is_portable_function_name(name))
+            .collect();
+
+        // Then
+        assert_eq!(portable, vec![true, true, true, false, false, false]);
+    }
+
     // --- new_store ---
 
     #[test]
@@ -208,7 +467,216 @@ mod tests {
         store.add_alias("gs".into(), "git stash".into());
 
         // Then
-        assert_eq!(store.aliases["gs"], "git stash");
+        assert_eq!(store.aliases["gs"].command, "git stash");
+    }
+
+    #[test]
+    fn given_existing_alias_with_metadata_when_updating_command_then_metadata_is_kept() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias("gs".into(), "git status".into());
+        let entry = store.alias_mut("gs").unwrap();
+        entry.description = Some("Short git status".into());
+        entry.tags = vec!["git".into()];
+        let created_at = entry.created_at.clone();
+
+        // When
+        store.add_alias("gs".into(), "git status -sb".into());
+
+        // Then
+        let entry = &store.aliases["gs"];
+        assert_eq!(entry.command, "git status -sb");
+        assert_eq!(entry.description.as_deref(), Some("Short git status"));
+        assert_eq!(entry.tags, vec!["git"]);
+        assert_eq!(entry.created_at, created_at);
+    }
+
+    // --- AliasFilter ---
+
+    #[test]
+    fn given_tag_and_search_filter_when_matching_then_only_matching_entries_pass() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias("gs".into(), "git status".into());
+        store.add_alias("ll".into(), "ls -la".into());
+        store.alias_mut("gs").unwrap().tags = vec!["git".into()];
+        let filter = AliasFilter {
+            tags: vec!["git".into()],
+            search: Some("STATUS".into()),
+            ..AliasFilter::default()
+        };
+
+        // When
+        let matched: Vec<&String> = store
+            .aliases
+            .iter()
+            .filter(|(name, entry)| filter.matches(name, entry))
+            .map(|(name, _)| name)
+            .collect();
+
+        // Then
+        assert_eq!(matched, vec!["gs"]);
+    }
+
+    #[test]
+    fn given_search_on_description_when_matching_then_entry_passes() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias("k".into(), "kubectl".into());
+        store.alias_mut("k").unwrap().description = Some("Kubernetes shortcut".into());
+        let filter = AliasFilter {
+            search: Some("kubernetes".into()),
+            ..AliasFilter::default()
+        };
+
+        // When
+        let result = filter.matches("k", &store.aliases["k"]);
+
+        // Then
+        assert!(result);
+    }
+
+    // --- from_json (historical schemas) ---
+
+    #[test]
+    fn given_v1_fixture_when_loading_then_commands_become_records() {
+        // Given
+        let json = include_str!("../tests/fixtures/store/v1.json");
+
+        // When
+        let (store, version) = AliasStore::from_json(json).unwrap();
+
+        // Then
+        assert_eq!(version, 1);
+        assert_eq!(store.version, CURRENT_VERSION);
+        assert_eq!(store.aliases.len(), 3);
+        assert_eq!(store.aliases["gs"].command, "git status");
+        assert_eq!(store.aliases["gs"].description, None);
+    }
+
+    #[test]
+    fn given_v1_fixture_with_functions_when_loading_then_functions_are_kept() {
+        // Given
+        let json = include_str!("../tests/fixtures/store/v1_functions.json");
+
+        // When
+        let (store, version) = AliasStore::from_json(json).unwrap();
+
+        // Then
+        assert_eq!(version, 1);
+        assert_eq!(store.aliases["gs"].command, "git status");
+        assert_eq!(
+            store.functions["mkcd"].body_for("powershell"),
+            Some("New-Item -ItemType Directory $args[0]\nSet-Location $args[0]")
+        );
+    }
+
+    #[test]
+    fn given_v1_fixture_with_records_when_loading_then_metadata_is_read() {
+        // Given
+        let json = include_str!("../tests/fixtures/store/v1_records.json");
+
+        // When
+        let (store, _) = AliasStore::from_json(json).unwrap();
+
+        // Then
+        assert_eq!(store.aliases["gs"].command, "git status");
+        assert_eq!(store.aliases["k"].author.as_deref(), Some("yumi"));
+        assert!(store.aliases["k"].has_tags(&["K8S".into()]));
+    }
+
+    #[test]
+    fn given_v2_fixture_when_loading_then_no_migration_happens() {
+        // Given
+        let json = include_str!("../tests/fixtures/store/v2.json");
+
+        // When
+        let (store, version) = AliasStore::from_json(json).unwrap();
+
+        // Then
+        assert_eq!(version, 2);
+        assert_eq!(
+            store.aliases["k"].description.as_deref(),
+            Some("Kubernetes shortcut")
+        );
+        assert_eq!(
+            store.functions["mkcd"].body_for("bash"),
+            Some("mkdir -p \"$1\"\ncd \"$1\"")
+        );
+    }
+
+    #[test]
+    fn given_store_from_newer_version_when_loading_then_returns_error() {
+        // Given
+        let json = r#"{"version": 999, "aliases": {}}"#;
+
+        // When
+        let result = AliasStore::from_json(json);
+
+        // Then
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_store_with_version_zero_when_loading_then_returns_error() {
+        // Given
+        let json = r#"{"version": 0, "aliases": {}}"#;
+
+        // When
+        let result = AliasStore::from_json(json);
+
+        // Then
+        let message = result.unwrap_err().to_string();
+        assert!(
+            message.contains("Unsupported alias store version 0"),
+            "{}",
+            message
+        );
+    }
+
+    // --- store_load (migration on disk) ---
+
+    #[test]
+    fn given_v1_file_when_loading_then_it_is_backed_up_and_upgraded() {
+        // Given
+        let dir = std::env::temp_dir().join(format!("akash-test-migrate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("aliases.json");
+        let original = include_str!("../tests/fixtures/store/v1.json");
+        std::fs::write(&path, original).unwrap();
+
+        // When
+        let store = AliasStore::store_load(Some(&path)).unwrap();
+
+        // Then
+        let backup = std::fs::read_to_string(dir.join("aliases.json.v1.bak")).unwrap();
+        let upgraded = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(store.aliases.len(), 3);
+        assert_eq!(backup, original);
+        assert!(upgraded.contains("\"version\": 2"));
+    }
+
+    #[test]
+    fn given_earlier_migration_backup_when_migrating_again_then_it_is_kept() {
+        // Given
+        let dir =
+            std::env::temp_dir().join(format!("akash-test-migrate-again-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("aliases.json");
+        let original = include_str!("../tests/fixtures/store/v1.json");
+        std::fs::write(dir.join("aliases.json.v1.bak"), "first backup").unwrap();
+        std::fs::write(&path, original).unwrap();
+
+        // When
+        AliasStore::store_load(Some(&path)).unwrap();
+
+        // Then
+        let first = std::fs::read_to_string(dir.join("aliases.json.v1.bak")).unwrap();
+        let second = std::fs::read_to_string(dir.join("aliases.json.v1.2.bak")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(first, "first backup");
+        assert_eq!(second, original);
     }
 
     // --- remove_alias ---
@@ -280,8 +748,8 @@ mod tests {
 
         // Then
         assert_eq!(list.len(), 2);
-        assert_eq!(list["a"], "alpha");
-        assert_eq!(list["b"], "bravo");
+        assert_eq!(list["a"].command, "alpha");
+        assert_eq!(list["b"].command, "bravo");
     }
 
     #[test]
@@ -299,6 +767,52 @@ mod tests {
         assert_eq!(keys, vec!["a", "m", "z"]);
     }
 
+    // --- add_function ---
+
+    #[test]
+    fn given_existing_alias_when_adding_function_with_same_name_then_alias_is_replaced() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias("up".into(), "cd ..".into());
+
+        // When
+        let is_new = store.add_function("up".into(), "cd ..\nls".into(), None);
+
+        // Then
+        assert!(is_new);
+        assert!(!store.aliases.contains_key("up"));
+        assert_eq!(store.functions["up"].body, "cd ..\nls");
+    }
+
+    #[test]
+    fn given_function_with_variant_when_getting_body_then_variant_wins_for_that_shell() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_function("up".into(), "cd ..".into(), None);
+        store.add_function("up".into(), "Set-Location ..".into(), Some("powershell"));
+
+        // When
+        let function = &store.functions["up"];
+
+        // Then
+        assert_eq!(function.body_for("powershell"), Some("Set-Location .."));
+        assert_eq!(function.body_for("bash"), Some("cd .."));
+    }
+
+    #[test]
+    fn given_existing_function_when_removing_it_then_returns_true() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_function("up".into(), "cd ..".into(), None);
+
+        // When
+        let result = store.remove_alias("up");
+
+        // Then
+        assert!(result);
+        assert!(store.functions.is_empty());
+    }
+
     // --- validate_alias_name ---
 
     #[test]
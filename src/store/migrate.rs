@@ -0,0 +1,92 @@
+//! Schema migrations for `aliases.json`.
+//!
+//! Every change to the stored JSON bumps `CURRENT_VERSION` and adds a step
+//! below, so files written by any older akash keep loading.
+//!
+//! History:
+//! - v1 (no `version` field): `{"aliases": {"gs": "git status"}}`, optionally with `functions`
+//! - v2: each alias is a record, `{"aliases": {"gs": {"command": "git status", ...}}}`
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use tracing::debug;
+
+/// Version written by this build of akash.
+pub const CURRENT_VERSION: u64 = 2;
+
+/// Schema version of a raw store. Files without a `version` field predate it: v1.
+pub fn version_of(value: &Value) -> Result<u64> {
+    match value.get("version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .with_context(|| format!("Invalid store version: {}", version)),
+    }
+}
+
+/// Upgrade a raw store to `CURRENT_VERSION`, one step at a time.
+/// Returns the version the store had before migrating.
+pub fn migrate(value: &mut Value) -> Result<u64> {
+    let original = version_of(value)?;
+
+    if original > CURRENT_VERSION {
+        anyhow::bail!(
+            "Alias store has version {}, but this akash only supports up to {}. Please upgrade akash.",
+            original,
+            CURRENT_VERSION
+        );
+    }
+    // v1 is the oldest schema: anything below was never written by akash
+    if original < 1 {
+        anyhow::bail!(
+            "Unsupported alias store version {}: versions start at 1",
+            original
+        );
+    }
+
+    let mut version = original;
+    while version < CURRENT_VERSION {
+        debug!(
+            "Migrating alias store from v{} to v{}",
+            version,
+            version + 1
+        );
+        match version {
+            1 => v1_to_v2(value)?,
+            _ => anyhow::bail!("Unsupported alias store version {}", version),
+        }
+        version += 1;
+    }
+
+    let object = value
+        .as_object_mut()
+        .context("Alias store must be a JSON object")?;
+    object.insert("version".to_string(), Value::from(CURRENT_VERSION));
+    Ok(original)
+}
+
+/// v1 -> v2: wrap each plain command string into a `{"command": ...}` record.
+/// Values that are already records are left untouched.
+fn v1_to_v2(value: &mut Value) -> Result<()> {
+    let object = value
+        .as_object_mut()
+        .context("Alias store must be a JSON object")?;
+
+    let aliases = object
+        .entry("aliases")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .context("\"aliases\" must be a JSON object")?;
+
+    for entry in aliases.values_mut() {
+        if let Value::String(command) = entry {
+            let mut record = Map::new();
+            record.insert(
+                "command".to_string(),
+                Value::String(std::mem::take(command)),
+            );
+            *entry = Value::Object(record);
+        }
+    }
+    Ok(())
+}
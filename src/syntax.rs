@@ -0,0 +1,927 @@
+use crate::shell::{Segment, Shell, ShellType};
+use crate::store::AliasStore;
+use anyhow::{Result, bail};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// A problem found by the built-in tokenizer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    /// 1-based line in the checked text
+    pub line: usize,
+    pub message: String,
+}
+
+/// Check the definitions `shell` gets from `store` before they are written.
+/// Fails with the name of every alias that would break the shell config.
+pub fn validate(shell: &dyn Shell, store: &AliasStore) -> Result<()> {
+    let problems: Vec<String> =
+        match broken_entries(shell.shell_type(), &shell.generate_entries(store)) {
+            Ok(broken) => broken
+                .iter()
+                .map(|(name, problem)| format!("{}: {}", name, problem))
+                .collect(),
+            Err(output) => vec![output],
+        };
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    bail!(
+        "Refusing to write aliases that would break {} (fix them with akash add):\n  {}",
+        shell.name(),
+        problems.join("\n  ")
+    )
+}
+
+/// Entries (name, definition) that would break `shell_type`, each with its problem.
+/// The shell itself has the last word when it is installed (`bash -n`, `fish --no-execute`, ...);
+/// otherwise the built-in tokenizer looks for unbalanced quotes, brackets, blocks and heredocs.
+/// Err with the shell's output when it rejects the block but none of the entries alone.
+pub fn broken_entries(
+    shell_type: ShellType,
+    entries: &[(String, String)],
+) -> Result<Vec<(String, String)>, String> {
+    let block = entries
+        .iter()
+        .map(|(_, text)| text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    match native_check(shell_type, &block) {
+        Some(Ok(())) => Ok(Vec::new()),
+        Some(Err(output)) => {
+            // The shell reports the whole block: check entries one by one to name the culprits
+            let culprits: Vec<(String, String)> = entries
+                .iter()
+                .filter_map(|(name, text)| match native_check(shell_type, text) {
+                    Some(Err(output)) => Some((name.clone(), first_line(&output).to_string())),
+                    _ => None,
+                })
+                .collect();
+            if culprits.is_empty() {
+                Err(output)
+            } else {
+                Ok(culprits)
+            }
+        }
+        None => Ok(tokenizer_broken_entries(shell_type, entries)),
+    }
+}
+
+/// Entries (name, definition) the built-in tokenizer rejects, each with its problem.
+/// Never starts a process: this is the check run by `akash hook` at every shell startup.
+pub fn tokenizer_broken_entries(
+    shell_type: ShellType,
+    entries: &[(String, String)],
+) -> Vec<(String, String)> {
+    entries
+        .iter()
+        .flat_map(|(name, text)| {
+            let multi_line = text.contains('\n');
+            check_syntax(shell_type, text)
+                .into_iter()
+                .map(move |error| match multi_line {
+                    true => (
+                        name.clone(),
+                        format!("{} (line {})", error.message, error.line),
+                    ),
+                    false => (name.clone(), error.message),
+                })
+        })
+        .collect()
+}
+
+/// Problems the built-in tokenizer finds in `text`, written for `shell_type`.
+pub fn check_syntax(shell_type: ShellType, text: &str) -> Vec<SyntaxError> {
+    let result = match shell_type {
+        ShellType::Bash | ShellType::Zsh | ShellType::Sh | ShellType::Ksh => check_posix(text),
+        ShellType::Fish => check_fish(text),
+        ShellType::PowerShell => check_powershell(text),
+        ShellType::Nushell => check_nushell(text),
+        ShellType::Cmd => Ok(check_cmd(text)),
+    };
+    // The scanners stop at the first error they cannot recover from
+    result.unwrap_or_else(|error| vec![error])
+}
+
+/// Parse `text` with the shell itself, without running it.
+/// None when the shell is not installed (or has no such mode).
+pub fn native_check(shell_type: ShellType, text: &str) -> Option<Result<(), String>> {
+    let (program, args): (&str, &[&str]) = match shell_type {
+        ShellType::Bash => ("bash", &["--norc", "--noprofile", "-n"]),
+        // -f skips .zshenv and the other startup files
+        ShellType::Zsh => ("zsh", &["-f", "-n"]),
+        ShellType::Sh => ("sh", &["-n"]),
+        ShellType::Ksh => ("ksh", &["-n"]),
+        ShellType::Fish => ("fish", &["--no-execute"]),
+        ShellType::PowerShell => return powershell_check(text),
+        ShellType::Nushell | ShellType::Cmd => return None,
+    };
+    run_check(Command::new(program).args(args), text)
+}
+
+/// Feed `text` to the checking command on stdin; None if it cannot be started.
+fn run_check(command: &mut Command, text: &str) -> Option<Result<(), String>> {
+    let mut child = match command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(error) => {
+            debug!("Syntax check unavailable: {}", error);
+            return None;
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A failed write shows up as a failed check below
+        let _ = stdin.write_all(text.as_bytes());
+        let _ = stdin.write_all(b"\n");
+    }
+    let output = child.wait_with_output().ok()?;
+    if output.status.success() {
+        return Some(Ok(()));
+    }
+    let message = [&output.stderr, &output.stdout]
+        .iter()
+        .map(|bytes| String::from_utf8_lossy(bytes).trim().to_string())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(Err(message))
+}
+
+/// PowerShell parses the text without running it, through its own parser API.
+/// The text goes over stdin as UTF-8: no temporary file another user could tamper with.
+fn powershell_check(text: &str) -> Option<Result<(), String>> {
+    let script = "$reader = New-Object System.IO.StreamReader([Console]::OpenStandardInput(), [System.Text.Encoding]::UTF8); \
+         $errors = $null; \
+         [void][System.Management.Automation.Language.Parser]::ParseInput($reader.ReadToEnd(), [ref]$null, [ref]$errors); \
+         if ($errors) { $errors | ForEach-Object { \"line $($_.Extent.StartLineNumber): $($_.Message)\" }; exit 1 }";
+
+    // PowerShell 7, then Windows PowerShell
+    ["pwsh", "powershell"].iter().find_map(|program| {
+        let mut command = Command::new(program);
+        command.args([
+            "-NoLogo",
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            script,
+        ]);
+        run_check(&mut command, text)
+    })
+}
+
+fn first_line(output: &str) -> &str {
+    output.lines().next().unwrap_or_default()
+}
+
+// ============================================================================
+// SCANNER
+// ============================================================================
+
+/// Walks characters while counting lines.
+struct Scanner {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl Scanner {
+    fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            position: 0,
+            line: 1,
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = *self.chars.get(self.position)?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    /// Character before the last one returned by `next`
+    fn before(&self) -> Option<char> {
+        self.position
+            .checked_sub(2)
+            .and_then(|index| self.chars.get(index).copied())
+    }
+
+    /// Skip to the end of the line, leaving the newline.
+    fn skip_line(&mut self) {
+        while self.peek().is_some_and(|c| c != '\n') {
+            self.next();
+        }
+    }
+
+    /// The rest of the current line, consuming its newline; None at the end of the text.
+    fn read_line(&mut self) -> Option<String> {
+        self.peek()?;
+        let mut line = String::new();
+        while let Some(c) = self.next() {
+            if c == '\n' {
+                break;
+            }
+            line.push(c);
+        }
+        Some(line)
+    }
+
+    /// Skip past `close`; `escape` skips the character after it. False if `close` never comes.
+    fn skip_quoted(&mut self, close: char, escape: Option<char>) -> bool {
+        while let Some(c) = self.next() {
+            if Some(c) == escape {
+                self.next();
+            } else if c == close {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// `skip_quoted`, failing with "unterminated <what>" from `line` if `close` never comes.
+    fn expect_closed(
+        &mut self,
+        close: char,
+        escape: Option<char>,
+        line: usize,
+        what: &str,
+    ) -> Result<(), SyntaxError> {
+        match self.skip_quoted(close, escape) {
+            true => Ok(()),
+            false => Err(error(line, format!("unterminated {}", what))),
+        }
+    }
+
+    /// Bare word starting with the last character returned, e.g. a keyword.
+    fn word_from(&mut self, first: char) -> String {
+        let mut word = first.to_string();
+        while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '_') {
+            word.push(c);
+            self.next();
+        }
+        word
+    }
+}
+
+fn error(line: usize, message: impl Into<String>) -> SyntaxError {
+    SyntaxError {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Whether `c` ends a word: whitespace, an operator or the end of the text
+fn ends_word(c: Option<char>) -> bool {
+    c.is_none_or(|c| c.is_whitespace() || ";&|()<>".contains(c))
+}
+
+// ============================================================================
+// POSIX (bash, zsh, sh, ksh)
+// ============================================================================
+
+#[derive(Clone, Copy, PartialEq)]
+enum PosixOpen {
+    /// `{ ...; }` command group or function body
+    Brace,
+    /// `( ... )` subshell or `$( ... )` substitution
+    Paren,
+    /// `${ ... }` parameter expansion
+    Parameter,
+    /// `case ... esac`, whose patterns end with a lone `)`
+    Case,
+}
+
+fn check_posix(text: &str) -> Result<Vec<SyntaxError>, SyntaxError> {
+    let mut scanner = Scanner::new(text);
+    let mut open: Vec<(PosixOpen, usize)> = Vec::new();
+    // Heredocs start at the end of the line that opens them: (delimiter, strip tabs, line)
+    let mut heredocs: Vec<(String, bool, usize)> = Vec::new();
+    let mut word_start = true;
+
+    while let Some(c) = scanner.next() {
+        let line = scanner.line;
+        match c {
+            '\n' => {
+                for (delimiter, strip_tabs, start) in heredocs.drain(..) {
+                    loop {
+                        let Some(body) = scanner.read_line() else {
+                            return Err(error(
+                                start,
+                                format!("heredoc '{}' is never closed", delimiter),
+                            ));
+                        };
+                        let body = if strip_tabs {
+                            body.trim_start_matches('\t')
+                        } else {
+                            &body
+                        };
+                        if body == delimiter {
+                            break;
+                        }
+                    }
+                }
+                word_start = true;
+                continue;
+            }
+            ' ' | '\t' | ';' | '&' | '|' => {
+                word_start = true;
+                continue;
+            }
+            '#' if word_start => {
+                scanner.skip_line();
+                continue;
+            }
+            '\\' => {
+                scanner.next();
+            }
+            '\'' => {
+                scanner.expect_closed('\'', None, line, "single quote")?;
+            }
+            '"' => {
+                scanner.expect_closed('"', Some('\\'), line, "double quote")?;
+            }
+            '`' => {
+                scanner.expect_closed('`', Some('\\'), line, "backquote")?;
+            }
+            '$' => match scanner.peek() {
+                Some('\'') => {
+                    scanner.next();
+                    scanner.expect_closed('\'', Some('\\'), line, "$'...' quote")?;
+                }
+                Some('(') => {
+                    scanner.next();
+                    open.push((PosixOpen::Paren, line));
+                    word_start = true;
+                    continue;
+                }
+                Some('{') => {
+                    scanner.next();
+                    open.push((PosixOpen::Parameter, line));
+                }
+                _ => {}
+            },
+            '(' => {
+                open.push((PosixOpen::Paren, line));
+                word_start = true;
+                continue;
+            }
+            ')' => match open.last() {
+                Some((PosixOpen::Paren, _)) => {
+                    open.pop();
+                }
+                // End of a case pattern
+                Some((PosixOpen::Case, _)) => {}
+                _ => return Err(error(line, "unexpected ')'")),
+            },
+            '{' if word_start && scanner.peek().is_none_or(char::is_whitespace) => {
+                open.push((PosixOpen::Brace, line));
+                continue;
+            }
+            '}' if matches!(open.last(), Some((PosixOpen::Parameter, _))) => {
+                open.pop();
+            }
+            '}' if word_start && ends_word(scanner.peek()) => match open.last() {
+                Some((PosixOpen::Brace, _)) => {
+                    open.pop();
+                }
+                _ => return Err(error(line, "unexpected '}'")),
+            },
+            // `<<` and `<<-` open a heredoc, `<<<` is a here-string
+            '<' if scanner.peek() == Some('<') && scanner.peek_at(1) != Some('<') => {
+                scanner.next();
+                let strip_tabs = scanner.peek() == Some('-');
+                if strip_tabs {
+                    scanner.next();
+                }
+                while scanner.peek().is_some_and(|c| c == ' ' || c == '\t') {
+                    scanner.next();
+                }
+                // Quotes around the delimiter only disable expansion in the body
+                let mut delimiter = String::new();
+                while !ends_word(scanner.peek()) {
+                    match scanner.next() {
+                        Some('\'' | '"' | '\\') => {}
+                        Some(c) => delimiter.push(c),
+                        None => break,
+                    }
+                }
+                if delimiter.is_empty() {
+                    return Err(error(line, "heredoc without a delimiter"));
+                }
+                heredocs.push((delimiter, strip_tabs, line));
+            }
+            c if word_start && c.is_alphabetic() => {
+                let word = scanner.word_from(c);
+                if ends_word(scanner.peek()) {
+                    match word.as_str() {
+                        "case" => open.push((PosixOpen::Case, line)),
+                        "esac" => match open.last() {
+                            Some((PosixOpen::Case, _)) => {
+                                open.pop();
+                            }
+                            _ => return Err(error(line, "unexpected 'esac'")),
+                        },
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        word_start = false;
+    }
+
+    let mut errors: Vec<SyntaxError> = open
+        .iter()
+        .map(|(kind, line)| {
+            let opening = match kind {
+                PosixOpen::Brace => "'{' is never closed",
+                PosixOpen::Paren => "'(' is never closed",
+                PosixOpen::Parameter => "'${' is never closed",
+                PosixOpen::Case => "'case' is never closed with 'esac'",
+            };
+            error(*line, opening)
+        })
+        .collect();
+    errors.extend(heredocs.iter().map(|(delimiter, _, line)| {
+        error(*line, format!("heredoc '{}' is never closed", delimiter))
+    }));
+    Ok(errors)
+}
+
+// ============================================================================
+// FISH
+// ============================================================================
+
+fn check_fish(text: &str) -> Result<Vec<SyntaxError>, SyntaxError> {
+    let mut scanner = Scanner::new(text);
+    let mut blocks: Vec<(String, usize)> = Vec::new();
+    let mut parens: Vec<usize> = Vec::new();
+    let mut command_start = true;
+    let mut after_else = false;
+
+    while let Some(c) = scanner.next() {
+        let line = scanner.line;
+        match c {
+            '\n' | ';' | '|' | '&' => {
+                command_start = true;
+                after_else = false;
+            }
+            ' ' | '\t' => {}
+            '#' if scanner.before().is_none_or(char::is_whitespace) => scanner.skip_line(),
+            '\\' => {
+                scanner.next();
+            }
+            '\'' => {
+                scanner.expect_closed('\'', Some('\\'), line, "single quote")?;
+                command_start = false;
+            }
+            '"' => {
+                scanner.expect_closed('"', Some('\\'), line, "double quote")?;
+                command_start = false;
+            }
+            '(' => {
+                parens.push(line);
+                command_start = true;
+            }
+            ')' => {
+                if parens.pop().is_none() {
+                    return Err(error(line, "unexpected ')'"));
+                }
+                command_start = false;
+            }
+            c if command_start && c.is_alphabetic() => {
+                let word = scanner.word_from(c);
+                if !ends_word(scanner.peek()) {
+                    command_start = false;
+                    continue;
+                }
+                match word.as_str() {
+                    // `else if` continues the same block
+                    "if" if after_else => {}
+                    "function" | "begin" | "if" | "while" | "for" | "switch" => {
+                        blocks.push((word.clone(), line))
+                    }
+                    "end" => {
+                        blocks
+                            .pop()
+                            .ok_or_else(|| error(line, "unexpected 'end'"))?;
+                    }
+                    _ => {}
+                }
+                after_else = word == "else";
+                command_start = matches!(word.as_str(), "and" | "or" | "not" | "else" | "begin");
+            }
+            _ => command_start = false,
+        }
+    }
+
+    let mut errors: Vec<SyntaxError> = blocks
+        .iter()
+        .map(|(keyword, line)| error(*line, format!("'{}' is never closed with 'end'", keyword)))
+        .collect();
+    errors.extend(
+        parens
+            .iter()
+            .map(|line| error(*line, "'(' is never closed")),
+    );
+    Ok(errors)
+}
+
+// ============================================================================
+// POWERSHELL
+// ============================================================================
+
+fn check_powershell(text: &str) -> Result<Vec<SyntaxError>, SyntaxError> {
+    let mut scanner = Scanner::new(text);
+    let mut open: Vec<(char, usize)> = Vec::new();
+
+    while let Some(c) = scanner.next() {
+        let line = scanner.line;
+        let token_start = scanner
+            .before()
+            .is_none_or(|before| before.is_whitespace() || ";(){}|".contains(before));
+        match c {
+            '#' if token_start => scanner.skip_line(),
+            '<' if scanner.peek() == Some('#') => {
+                scanner.next();
+                let mut closed = false;
+                while let Some(c) = scanner.next() {
+                    if c == '#' && scanner.peek() == Some('>') {
+                        scanner.next();
+                        closed = true;
+                        break;
+                    }
+                }
+                if !closed {
+                    return Err(error(line, "block comment '<#' is never closed"));
+                }
+            }
+            '`' => {
+                scanner.next();
+            }
+            // Here-strings: @' or @" at the end of a line, closed by '@ or "@ at the start of one
+            '@' if matches!(scanner.peek(), Some('\'' | '"'))
+                && scanner.peek_at(1).is_none_or(|c| c == '\n' || c == '\r') =>
+            {
+                let quote = scanner.next().unwrap_or('\'');
+                let closing = format!("{}@", quote);
+                scanner.read_line();
+                loop {
+                    match scanner.read_line() {
+                        Some(body) if body.starts_with(&closing) => break,
+                        Some(_) => {}
+                        None => return Err(error(line, "here-string is never closed")),
+                    }
+                }
+            }
+            '\'' => {
+                // '' inside single quotes is an escaped quote
+                let closed = loop {
+                    match scanner.next() {
+                        Some('\'') if scanner.peek() == Some('\'') => {
+                            scanner.next();
+                        }
+                        Some('\'') => break true,
+                        Some(_) => {}
+                        None => break false,
+                    }
+                };
+                if !closed {
+                    return Err(error(line, "unterminated single quote"));
+                }
+            }
+            '"' => {
+                scanner.expect_closed('"', Some('`'), line, "double quote")?;
+            }
+            '{' | '(' => open.push((c, line)),
+            '}' | ')' => {
+                let expected = if c == '}' { '{' } else { '(' };
+                match open.pop() {
+                    Some((opening, _)) if opening == expected => {}
+                    Some((opening, opened)) => {
+                        return Err(error(
+                            line,
+                            format!(
+                                "unexpected '{}': '{}' from line {} is still open",
+                                c, opening, opened
+                            ),
+                        ));
+                    }
+                    None => return Err(error(line, format!("unexpected '{}'", c))),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(open
+        .iter()
+        .map(|(opening, line)| error(*line, format!("'{}' is never closed", opening)))
+        .collect())
+}
+
+// ============================================================================
+// NUSHELL
+// ============================================================================
+
+fn check_nushell(text: &str) -> Result<Vec<SyntaxError>, SyntaxError> {
+    let mut scanner = Scanner::new(text);
+    let mut open: Vec<(char, usize)> = Vec::new();
+
+    while let Some(c) = scanner.next() {
+        let line = scanner.line;
+        match c {
+            '#' if scanner
+                .before()
+                .is_none_or(|b| b.is_whitespace() || "([{;|".contains(b)) =>
+            {
+                scanner.skip_line()
+            }
+            '\'' | '`' => {
+                scanner.expect_closed(c, None, line, &format!("{} quote", c))?;
+            }
+            '"' => {
+                scanner.expect_closed('"', Some('\\'), line, "double quote")?;
+            }
+            '{' | '(' | '[' => open.push((c, line)),
+            '}' | ')' | ']' => {
+                let expected = match c {
+                    '}' => '{',
+                    ')' => '(',
+                    _ => '[',
+                };
+                match open.pop() {
+                    Some((opening, _)) if opening == expected => {}
+                    _ => return Err(error(line, format!("unexpected '{}'", c))),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(open
+        .iter()
+        .map(|(opening, line)| error(*line, format!("'{}' is never closed", opening)))
+        .collect())
+}
+
+// ============================================================================
+// CMD (doskey)
+// ============================================================================
+
+/// doskey macros are single lines of text: nothing can be unbalanced. The only problem is
+/// a `{N}` placeholder above 9, which the Cmd backend leaves as-is since doskey has no `$10`.
+fn check_cmd(text: &str) -> Vec<SyntaxError> {
+    let Some(segments) = crate::shell::parse_placeholders(text) else {
+        return Vec::new();
+    };
+    segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Arg(n) if *n > 9 => Some(error(
+                1,
+                format!("doskey macros only take $1 to $9, not {{{}}}", n),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// UNIT TESTS
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell;
+
+    fn messages(shell_type: ShellType, text: &str) -> Vec<String> {
+        check_syntax(shell_type, text)
+            .into_iter()
+            .map(|error| format!("{}: {}", error.line, error.message))
+            .collect()
+    }
+
+    // --- POSIX ---
+
+    #[test]
+    fn given_valid_posix_code_when_checking_then_no_errors() {
+        // Given
+        let text = r#"alias gs='git status'
+alias say='echo '\''hi'\'''
+gco() { git checkout "$1" "${@:2}"; }
+mkcd() {
+    mkdir -p "$1" && cd "$1" # go there }
+    case "$1" in
+        a) echo a ;;
+        (b) echo $(date) ;;
+    esac
+    cat <<-EOF
+	{ not code
+	EOF
+    echo ${HOME} a} {b
+}"#;
+
+        // When
+        let errors = messages(ShellType::Bash, text);
+
+        // Then
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn given_broken_posix_code_when_checking_then_reports_line() {
+        // Given
+        let cases = [
+            ("alias x='oops", "1: unterminated single quote"),
+            ("f() { echo \"$1; }", "1: unterminated double quote"),
+            ("f() {\n  echo hi\n", "1: '{' is never closed"),
+            ("echo a\n}", "2: unexpected '}'"),
+            ("echo $(date", "1: '(' is never closed"),
+            ("cat <<EOF\nbody\n", "1: heredoc 'EOF' is never closed"),
+        ];
+
+        for (text, expected) in cases {
+            // When
+            let errors = messages(ShellType::Bash, text);
+
+            // Then
+            assert_eq!(errors, vec![expected.to_string()], "for {:?}", text);
+        }
+    }
+
+    // --- Fish ---
+
+    #[test]
+    fn given_fish_blocks_when_checking_then_matches_end() {
+        // Given
+        let valid = "function f\n    if test -n (echo $argv)\n        echo 'end'\n    else if true\n        echo b # end\n    end\nend";
+        let unclosed = "function f\n    if true\n        echo a\nend";
+        let extra = "alias gs 'git status'\nend";
+
+        // When
+        let results = [
+            messages(ShellType::Fish, valid),
+            messages(ShellType::Fish, unclosed),
+            messages(ShellType::Fish, extra),
+        ];
+
+        // Then
+        assert!(results[0].is_empty(), "{:?}", results[0]);
+        assert_eq!(results[1], vec!["1: 'function' is never closed with 'end'"]);
+        assert_eq!(results[2], vec!["2: unexpected 'end'"]);
+    }
+
+    // --- PowerShell ---
+
+    #[test]
+    fn given_powershell_code_when_checking_then_finds_unbalanced_braces() {
+        // Given
+        let valid = "function gs { git status @args }\nfunction q { 'it''s {'; \"`\"}\" } # }\n<# { #>\n$s = @'\n}\n'@";
+        let stray = "function gs { git status } }";
+        let mismatched = "function f { (Get-Date }";
+
+        // When
+        let results = [
+            messages(ShellType::PowerShell, valid),
+            messages(ShellType::PowerShell, stray),
+            messages(ShellType::PowerShell, mismatched),
+        ];
+
+        // Then
+        assert!(results[0].is_empty(), "{:?}", results[0]);
+        assert_eq!(results[1], vec!["1: unexpected '}'"]);
+        assert_eq!(
+            results[2],
+            vec!["1: unexpected '}': '(' from line 1 is still open"]
+        );
+    }
+
+    // --- Nushell ---
+
+    #[test]
+    fn given_nushell_code_when_checking_then_matches_brackets() {
+        // Given
+        let valid = "def --wrapped gco [p1, ...rest] { git checkout $p1 ...$rest }";
+        let broken = "def f [] { ls | where size > 1mb ";
+
+        // When
+        let results = [
+            messages(ShellType::Nushell, valid),
+            messages(ShellType::Nushell, broken),
+        ];
+
+        // Then
+        assert!(results[0].is_empty());
+        assert_eq!(results[1], vec!["1: '{' is never closed"]);
+    }
+
+    // --- Cmd ---
+
+    #[test]
+    fn given_placeholder_above_nine_when_validating_cmd_then_refuses_it() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias(String::from("gco"), String::from("git checkout {1}"));
+        store.add_alias(String::from("many"), String::from("echo {1} {10}"));
+        let cmd = shell::get_shell(Some(ShellType::Cmd)).unwrap();
+
+        // When
+        let result = validate(cmd.as_ref(), &store);
+
+        // Then
+        let message = result.unwrap_err().to_string();
+        assert!(
+            message.contains("many: doskey macros only take $1 to $9, not {10}"),
+            "{}",
+            message
+        );
+        assert!(!message.contains("gco: "), "{}", message);
+    }
+
+    // --- run_check ---
+
+    #[cfg(unix)]
+    #[test]
+    fn given_failing_check_with_both_outputs_when_running_then_they_are_on_separate_lines() {
+        // Given
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo out; echo err >&2; exit 1"]);
+
+        // When
+        let result = run_check(&mut command, "");
+
+        // Then
+        if let Some(result) = result {
+            assert_eq!(result, Err(String::from("err\nout")));
+        }
+    }
+
+    // --- validate ---
+
+    #[test]
+    fn given_broken_function_when_validating_then_names_it() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias(String::from("gs"), String::from("git status"));
+        store.add_alias(String::from("bad"), String::from("echo {1} '"));
+        let bash = shell::get_shell(Some(ShellType::Bash)).unwrap();
+
+        // When
+        let result = validate(bash.as_ref(), &store);
+
+        // Then
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("bad: "), "{}", message);
+        assert!(!message.contains("gs: "), "{}", message);
+    }
+
+    #[test]
+    fn given_broken_entry_when_listing_broken_entries_then_only_it_is_returned() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias(String::from("gs"), String::from("git status"));
+        store.add_function(String::from("bad"), String::from("ls | each {"), None);
+        let nushell = shell::get_shell(Some(ShellType::Nushell)).unwrap();
+
+        // When
+        let broken = broken_entries(ShellType::Nushell, &nushell.generate_entries(&store));
+
+        // Then
+        let names: Vec<String> = broken.unwrap().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec![String::from("bad")]);
+    }
+
+    #[test]
+    fn given_broken_entry_when_tokenizing_entries_then_only_it_is_returned() {
+        // Given
+        let mut store = AliasStore::new_store();
+        store.add_alias(String::from("gs"), String::from("git status"));
+        store.add_alias(String::from("bad"), String::from("echo {1} '"));
+        let bash = shell::get_shell(Some(ShellType::Bash)).unwrap();
+
+        // When
+        let broken = tokenizer_broken_entries(ShellType::Bash, &bash.generate_entries(&store));
+
+        // Then
+        let names: Vec<String> = broken.into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec![String::from("bad")]);
+    }
+}